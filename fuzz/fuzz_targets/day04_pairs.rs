@@ -0,0 +1,12 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+
+#[path = "../../src/bin/04.rs"]
+#[allow(dead_code, unused_attributes)]
+mod day04;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(input) = std::str::from_utf8(data) {
+        let _ = day04::parse(input);
+    }
+});
@@ -0,0 +1,16 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+
+#[path = "../../src/bin/05.rs"]
+#[allow(dead_code, unused_attributes)]
+mod day05;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(input) = std::str::from_utf8(data) {
+        // Moves are validated while parsing, so performing them must not panic either.
+        if let Ok(procedure) = day05::parse_input(input) {
+            day05::part_one(&procedure);
+            day05::part_two(&procedure);
+        }
+    }
+});
@@ -0,0 +1,22 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+
+#[path = "../../src/bin/07.rs"]
+#[allow(dead_code, unused_attributes, private_interfaces)]
+mod day07;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(input) = std::str::from_utf8(data) {
+        input.lines().for_each(|line| {
+            let _ = day07::parse_line(line);
+        });
+
+        let options = day07::TranscriptOptions {
+            create_missing_dirs: data.len() % 2 == 0,
+        };
+        if let Ok(tree) = day07::parse_transcript(input, &options) {
+            day07::part_one(&tree);
+            day07::part_two(&tree);
+        }
+    }
+});
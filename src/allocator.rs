@@ -0,0 +1,163 @@
+//! Opt-in global allocator that counts allocations per solution part.
+//!
+//! Enable with `--features count-allocations`, e.g. `cargo solve 07 --features count-allocations`.
+//! Without the feature the system allocator is used untouched and [`stats`] returns `None`.
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    fmt::Display,
+    sync::atomic::{AtomicUsize, Ordering::Relaxed},
+};
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+static REALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+static BYTES_ALLOCATED: AtomicUsize = AtomicUsize::new(0);
+static LIVE: AtomicUsize = AtomicUsize::new(0);
+static PEAK: AtomicUsize = AtomicUsize::new(0);
+static BASELINE: AtomicUsize = AtomicUsize::new(0);
+
+/// Wraps the [`System`] allocator and keeps running totals in global counters.
+pub struct CountingAllocator;
+
+impl CountingAllocator {
+    fn grow_live(by: usize) {
+        let live = LIVE.fetch_add(by, Relaxed) + by;
+        PEAK.fetch_max(live, Relaxed);
+    }
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc(layout);
+        if !ptr.is_null() {
+            ALLOCATIONS.fetch_add(1, Relaxed);
+            BYTES_ALLOCATED.fetch_add(layout.size(), Relaxed);
+            Self::grow_live(layout.size());
+        }
+        ptr
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc_zeroed(layout);
+        if !ptr.is_null() {
+            ALLOCATIONS.fetch_add(1, Relaxed);
+            BYTES_ALLOCATED.fetch_add(layout.size(), Relaxed);
+            Self::grow_live(layout.size());
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+        LIVE.fetch_sub(layout.size(), Relaxed);
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let new_ptr = System.realloc(ptr, layout, new_size);
+        if !new_ptr.is_null() {
+            REALLOCATIONS.fetch_add(1, Relaxed);
+            if new_size > layout.size() {
+                let grown = new_size - layout.size();
+                BYTES_ALLOCATED.fetch_add(grown, Relaxed);
+                Self::grow_live(grown);
+            } else {
+                LIVE.fetch_sub(layout.size() - new_size, Relaxed);
+            }
+        }
+        new_ptr
+    }
+}
+
+/// Allocation counters since the last call to [`reset`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct AllocStats {
+    pub allocations: usize,
+    pub reallocations: usize,
+    /// Total bytes requested, including growth through `realloc`.
+    pub bytes_allocated: usize,
+    /// Highest number of live bytes on top of what was live at [`reset`].
+    pub peak_bytes: usize,
+}
+
+impl Display for AllocStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "allocations: {}, reallocations: {}, allocated: {}, peak: {}",
+            self.allocations,
+            self.reallocations,
+            format_bytes(self.bytes_allocated),
+            format_bytes(self.peak_bytes)
+        )
+    }
+}
+
+fn format_bytes(bytes: usize) -> String {
+    const UNITS: [&str; 4] = ["B", "KiB", "MiB", "GiB"];
+
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024_f64 && unit < UNITS.len() - 1 {
+        value /= 1024_f64;
+        unit += 1;
+    }
+
+    if unit == 0 {
+        format!("{} {}", bytes, UNITS[0])
+    } else {
+        format!("{:.2} {}", value, UNITS[unit])
+    }
+}
+
+/// Whether the counting allocator is installed as the global allocator.
+pub const fn enabled() -> bool {
+    cfg!(feature = "count-allocations")
+}
+
+/// Zeroes all counters and makes the current live bytes the baseline for the peak.
+pub fn reset() {
+    ALLOCATIONS.store(0, Relaxed);
+    REALLOCATIONS.store(0, Relaxed);
+    BYTES_ALLOCATED.store(0, Relaxed);
+    let live = LIVE.load(Relaxed);
+    BASELINE.store(live, Relaxed);
+    PEAK.store(live, Relaxed);
+}
+
+/// Counters since the last [`reset`], or `None` if the counting allocator is not enabled.
+pub fn stats() -> Option<AllocStats> {
+    enabled().then(|| AllocStats {
+        allocations: ALLOCATIONS.load(Relaxed),
+        reallocations: REALLOCATIONS.load(Relaxed),
+        bytes_allocated: BYTES_ALLOCATED.load(Relaxed),
+        peak_bytes: PEAK.load(Relaxed).saturating_sub(BASELINE.load(Relaxed)),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_bytes() {
+        assert_eq!(format_bytes(0), "0 B");
+        assert_eq!(format_bytes(1023), "1023 B");
+        assert_eq!(format_bytes(1536), "1.50 KiB");
+        assert_eq!(format_bytes(3 * 1024 * 1024), "3.00 MiB");
+    }
+
+    #[test]
+    fn test_stats_follow_feature() {
+        reset();
+        let v: Vec<u64> = (0..1024).collect();
+        let stats = stats();
+        drop(v);
+
+        // Other test threads allocate and free concurrently, so byte counts and the peak can
+        // be off in either direction. Only the allocation made here is certain.
+        if enabled() {
+            assert!(stats.unwrap().allocations >= 1);
+        } else {
+            assert_eq!(stats, None);
+        }
+    }
+}
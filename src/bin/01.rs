@@ -1,31 +1,160 @@
-#![feature(binary_heap_into_iter_sorted)]
-use std::{collections::BinaryHeap, ops::Add};
+use advent_of_code::helpers::{top_k, TopK};
+use std::{
+    cmp::Reverse,
+    fmt::Display,
+    iter::Peekable,
+    num::{IntErrorKind, NonZeroUsize},
+};
 
-pub fn calories(input: &str) -> impl Iterator<Item = u32> + '_ {
-    let mut it = input.lines();
+#[derive(Debug, PartialEq, Eq)]
+pub enum ErrorKind {
+    InvalidCalories(String),
+    CaloriesOutOfRange(String),
+    TotalOutOfRange,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct ParseError {
+    line: usize,
+    kind: ErrorKind,
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "line {}: ", self.line)?;
+        match &self.kind {
+            ErrorKind::InvalidCalories(line) => write!(f, "expected calories, found {line:?}"),
+            ErrorKind::CaloriesOutOfRange(line) => write!(f, "calories out of range: {line:?}"),
+            ErrorKind::TotalOutOfRange => write!(f, "total calories of the elf out of range"),
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+fn is_separator(line: &str) -> bool {
+    line.trim().is_empty()
+}
+
+fn group_total<'a>(
+    lines: &mut Peekable<impl Iterator<Item = (&'a str, usize)>>,
+) -> Result<u64, ParseError> {
+    let mut total = 0_u64;
+
+    while let Some((line, line_no)) = lines.next_if(|(line, _)| !is_separator(line)) {
+        let error = |kind| ParseError {
+            line: line_no,
+            kind,
+        };
+        let item: u64 = line.trim().parse().map_err(|e: std::num::ParseIntError| {
+            error(match e.kind() {
+                IntErrorKind::PosOverflow => ErrorKind::CaloriesOutOfRange(line.to_string()),
+                _ => ErrorKind::InvalidCalories(line.to_string()),
+            })
+        })?;
+        total = total
+            .checked_add(item)
+            .ok_or_else(|| error(ErrorKind::TotalOutOfRange))?;
+    }
+
+    Ok(total)
+}
+
+/// Total calories per elf. Groups are separated by one or more blank or whitespace-only lines,
+/// any other line that is not a number is reported as an error.
+pub fn calories(input: &str) -> impl Iterator<Item = Result<u64, ParseError>> + '_ {
+    let mut lines = input.lines().zip(1..).peekable();
     std::iter::from_fn(move || {
-        it.by_ref()
-            .map_while(|line| line.parse().ok())
-            .reduce(Add::add)
+        while lines.next_if(|(line, _)| is_separator(line)).is_some() {}
+        lines.peek()?;
+        Some(group_total(&mut lines))
     })
 }
 
-pub fn part_one(input: &str) -> Option<u32> {
-    calories(input).max()
+pub fn parse(input: &str) -> Result<Vec<u64>, ParseError> {
+    calories(input).collect()
+}
+
+pub fn part_one(totals: &[u64]) -> Option<u64> {
+    totals.iter().copied().max()
+}
+
+/// `None` if there are no elves or the three largest totals don't fit in a `u64`.
+pub fn part_two(totals: &[u64]) -> Option<u64> {
+    let top = top_k(totals.iter().copied(), 3);
+    if top.is_empty() {
+        return None;
+    }
+    top.into_iter().try_fold(0, u64::checked_add)
 }
 
-pub fn part_two(input: &str) -> Option<u32> {
-    calories(input)
-        .collect::<BinaryHeap<u32>>()
-        .into_iter_sorted()
-        .take(3)
-        .reduce(Add::add)
+#[derive(Debug, PartialEq)]
+pub struct Summary {
+    pub elves: usize,
+    /// Index and total of the elves carrying the most calories, most first.
+    /// Ties go to the elf that comes first.
+    pub top: Vec<(usize, u64)>,
+    pub mean: f64,
+    pub median: f64,
+}
+
+/// Collects the top `n` carriers and summary statistics in a single pass over the totals.
+/// The median needs all totals, so they are kept and memory use grows with the number of
+/// elves. `None` if there are no elves, panics if `n` is zero.
+pub fn summarize(totals: impl IntoIterator<Item = u64>, n: usize) -> Option<Summary> {
+    assert!(n > 0, "Must summarize at least one top carrier");
+    let mut top = TopK::new(n);
+    let mut totals_seen = vec![];
+    let mut sum = 0_u128;
+
+    for (idx, total) in totals.into_iter().enumerate() {
+        top.push((total, Reverse(idx)));
+        totals_seen.push(total);
+        sum += u128::from(total);
+    }
+
+    let elves = totals_seen.len();
+    if elves == 0 {
+        return None;
+    }
+
+    let (_, &mut upper, _) = totals_seen.select_nth_unstable(elves / 2);
+    let median = if elves % 2 == 0 {
+        let lower = *totals_seen[..elves / 2].iter().max().unwrap();
+        (lower as f64 + upper as f64) / 2_f64
+    } else {
+        upper as f64
+    };
+
+    Some(Summary {
+        elves,
+        top: top
+            .into_sorted_vec()
+            .into_iter()
+            .map(|(total, Reverse(idx))| (idx, total))
+            .collect(),
+        mean: sum as f64 / elves as f64,
+        median,
+    })
 }
 
 fn main() {
+    let mut args = pico_args::Arguments::from_env();
+    let top: Option<NonZeroUsize> =
+        advent_of_code::unwrap_or_exit(args.opt_value_from_str("--top"));
+
     let input = &advent_of_code::read_file("inputs", 1);
-    advent_of_code::solve!(1, part_one, input);
-    advent_of_code::solve!(2, part_two, input);
+    let totals = advent_of_code::unwrap_or_exit(advent_of_code::parse!(parse, input));
+    advent_of_code::solve!(1, part_one, &totals);
+    advent_of_code::solve!(2, part_two, &totals);
+
+    if let Some(summary) = summarize(totals, top.map_or(3, NonZeroUsize::get)) {
+        println!("Elves: {}", summary.elves);
+        for (rank, (idx, total)) in summary.top.iter().enumerate() {
+            println!("#{}: elf {} with {} calories", rank + 1, idx + 1, total);
+        }
+        println!("Mean: {:.2}, median: {:.1}", summary.mean, summary.median);
+    }
 }
 
 #[cfg(test)]
@@ -35,12 +164,75 @@ mod tests {
     #[test]
     fn test_part_one() {
         let input = advent_of_code::read_file("examples", 1);
-        assert_eq!(part_one(&input), Some(24000));
+        assert_eq!(part_one(&parse(&input).unwrap()), Some(24000));
     }
 
     #[test]
     fn test_part_two() {
         let input = advent_of_code::read_file("examples", 1);
-        assert_eq!(part_two(&input), Some(45000));
+        assert_eq!(part_two(&parse(&input).unwrap()), Some(45000));
+    }
+
+    #[test]
+    fn test_summarize() {
+        let input = advent_of_code::read_file("examples", 1);
+        assert_eq!(
+            summarize(parse(&input).unwrap(), 4),
+            Some(Summary {
+                elves: 5,
+                top: vec![(3, 24000), (2, 11000), (4, 10000), (0, 6000)],
+                mean: 11000_f64,
+                median: 10000_f64,
+            })
+        );
+        assert_eq!(summarize([1, 3, 2, 3], 2).unwrap().top, [(1, 3), (3, 3)]);
+        assert_eq!(summarize([1, 3, 2, 3], 2).unwrap().median, 2.5);
+        assert_eq!(summarize([], 3), None);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_summarize_nothing() {
+        summarize([1, 2, 3], 0);
+    }
+
+    #[test]
+    fn test_separators() {
+        assert_eq!(parse("1\n2\n \n3\n\t\n\n4\r\n5\n"), Ok(vec![3, 3, 9]));
+        assert_eq!(parse("\n\n1\n\n"), Ok(vec![1]));
+        assert_eq!(parse(""), Ok(vec![]));
+    }
+
+    #[test]
+    fn test_invalid_lines() {
+        let error = |input| parse(input).unwrap_err().to_string();
+        assert_eq!(
+            error("1000\n2000\nabc\n"),
+            "line 3: expected calories, found \"abc\""
+        );
+        assert_eq!(
+            error("1000\n\n-5\n"),
+            "line 3: expected calories, found \"-5\""
+        );
+        assert_eq!(
+            error("18446744073709551616\n"),
+            "line 1: calories out of range: \"18446744073709551616\""
+        );
+        assert_eq!(
+            error("1\n\n18446744073709551615\n1\n"),
+            "line 4: total calories of the elf out of range"
+        );
+    }
+
+    #[test]
+    fn test_large_totals() {
+        let totals = parse("4000000000\n4000000000\n\n5000000000\n").unwrap();
+        assert_eq!(part_one(&totals), Some(8000000000));
+        assert_eq!(part_two(&totals), Some(13000000000));
+
+        let half = u64::MAX / 2 + 1;
+        assert_eq!(part_one(&[half, half]), Some(half));
+        assert_eq!(part_two(&[half, half]), None);
+        assert_eq!(part_two(&[half, half - 1]), Some(u64::MAX));
     }
 }
@@ -1,77 +1,376 @@
-#![feature(array_chunks)]
-const ROCK: u8 = b'A';
-const PAPER: u8 = b'B';
-const SCISSOR: u8 = b'C';
-
-const _ROCK: u32 = 1;
-const _PAPER: u32 = 2;
-const _SCISSOR: u32 = 3;
-
-fn take_lines(input: &str) -> impl Iterator<Item = (u8, u8)> + '_ {
-    input
-        .as_bytes()
-        .array_chunks::<4>()
-        .map(|&[a, _, b, _]| (a, b))
-}
-
-pub fn part_one(input: &str) -> Option<u32> {
-    let sum = take_lines(input)
-        .map(|(opp, me)| {
-            const MY_ROCK: u8 = b'X';
-            const MY_PAPER: u8 = b'Y';
-            const MY_SCISSOR: u8 = b'Z';
-
-            match (me, opp) {
-                (MY_ROCK, PAPER) => _ROCK,
-                (MY_ROCK, ROCK) => 3 + _ROCK,
-                (MY_ROCK, SCISSOR) => 6 + _ROCK,
-
-                (MY_PAPER, SCISSOR) => _PAPER,
-                (MY_PAPER, PAPER) => 3 + _PAPER,
-                (MY_PAPER, ROCK) => 6 + _PAPER,
-
-                (MY_SCISSOR, SCISSOR) => 3 + _SCISSOR,
-                (MY_SCISSOR, PAPER) => 6 + _SCISSOR,
-                (MY_SCISSOR, ROCK) => _SCISSOR,
-
-                _ => unsafe { std::hint::unreachable_unchecked() },
+use std::fmt::Display;
+
+/// The classic game, in the config format understood by [`Rules::from_config`].
+const STANDARD_RULES: &str = "\
+shape rock A X 1
+shape paper B Y 2
+shape scissors C Z 3
+beats rock scissors
+beats paper rock
+beats scissors paper
+outcome lose X 0
+outcome draw Y 3
+outcome win Z 6
+";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Outcome {
+    Lose,
+    Draw,
+    Win,
+}
+
+impl Outcome {
+    const ALL: [Outcome; 3] = [Outcome::Lose, Outcome::Draw, Outcome::Win];
+
+    fn parse(name: &str) -> Option<Self> {
+        match name {
+            "lose" => Some(Outcome::Lose),
+            "draw" => Some(Outcome::Draw),
+            "win" => Some(Outcome::Win),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Shape {
+    name: String,
+    opponent_symbol: char,
+    own_symbol: char,
+    score: u32,
+}
+
+/// A game of hand shapes where every pair of different shapes has a winner.
+///
+/// Both parts are answered from two tables derived from the rules: the score of every
+/// (opponent, own) combination, and the shape to play for every (opponent, outcome).
+#[derive(Debug)]
+pub struct Rules {
+    shapes: Vec<Shape>,
+    outcome_symbols: [char; 3],
+    scores: Vec<u64>,
+    choices: Vec<usize>,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct ConfigError {
+    line: usize,
+    message: String,
+}
+
+impl Display for ConfigError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "rules, line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+impl Rules {
+    pub fn standard() -> Self {
+        Self::from_config(STANDARD_RULES).expect("Standard rules to be valid")
+    }
+
+    /// Reads rules from one directive per line, `#` starts a comment:
+    /// - `shape <name> <opponent symbol> <own symbol> <score>`
+    /// - `beats <winner> <loser>`
+    /// - `outcome <lose|draw|win> <symbol> <score>`
+    pub fn from_config(config: &str) -> Result<Self, ConfigError> {
+        let mut shapes: Vec<Shape> = vec![];
+        let mut beats: Vec<(usize, usize, usize)> = vec![];
+        let mut outcomes: [Option<(char, u32)>; 3] = [None; 3];
+        let mut last_line = 0;
+
+        for (line, line_no) in config.lines().zip(1..) {
+            last_line = line_no;
+            let error = |message: String| ConfigError {
+                line: line_no,
+                message,
+            };
+            let symbol = |word: &str| {
+                let mut chars = word.chars();
+                match (chars.next(), chars.next()) {
+                    (Some(ch), None) if !ch.is_whitespace() => Ok(ch),
+                    _ => Err(error(format!(
+                        "expected a single character, found {word:?}"
+                    ))),
+                }
+            };
+            let score = |word: &str| {
+                word.parse::<u32>()
+                    .map_err(|_| error(format!("expected a score, found {word:?}")))
+            };
+            let shape = |shapes: &[Shape], name: &str| {
+                shapes
+                    .iter()
+                    .position(|shape| shape.name == name)
+                    .ok_or_else(|| error(format!("unknown shape {name:?}")))
+            };
+
+            let words: Vec<&str> = line
+                .split('#')
+                .next()
+                .unwrap_or_default()
+                .split_whitespace()
+                .collect();
+
+            match words.as_slice() {
+                [] => {}
+                ["shape", name, opponent, own, points] => {
+                    if shapes.iter().any(|shape| shape.name == *name) {
+                        return Err(error(format!("shape {name:?} is defined twice")));
+                    }
+                    let shape = Shape {
+                        name: name.to_string(),
+                        opponent_symbol: symbol(opponent)?,
+                        own_symbol: symbol(own)?,
+                        score: score(points)?,
+                    };
+                    if shapes.iter().any(|other| {
+                        other.opponent_symbol == shape.opponent_symbol
+                            || other.own_symbol == shape.own_symbol
+                    }) {
+                        return Err(error(format!("symbols of {name:?} are already taken")));
+                    }
+                    shapes.push(shape);
+                }
+                ["beats", winner, loser] => {
+                    let winner = shape(&shapes, winner)?;
+                    let loser = shape(&shapes, loser)?;
+                    if winner == loser {
+                        return Err(error("a shape cannot beat itself".to_string()));
+                    }
+                    beats.push((winner, loser, line_no));
+                }
+                ["outcome", name, sym, points] => {
+                    let outcome = Outcome::parse(name)
+                        .ok_or_else(|| error(format!("unknown outcome {name:?}")))?;
+                    if outcomes[outcome as usize].is_some() {
+                        return Err(error(format!("outcome {name:?} is defined twice")));
+                    }
+                    let sym = symbol(sym)?;
+                    if outcomes.iter().flatten().any(|&(other, _)| other == sym) {
+                        return Err(error(format!(
+                            "symbol of outcome {name:?} is already taken"
+                        )));
+                    }
+                    outcomes[outcome as usize] = Some((sym, score(points)?));
+                }
+                _ => return Err(error(format!("unknown directive {:?}", line.trim()))),
+            }
+        }
+
+        let n = shapes.len();
+        let error = |message: String| ConfigError {
+            line: last_line,
+            message,
+        };
+
+        let mut winner = vec![None; n * n];
+        for (a, b, line_no) in beats {
+            if winner[b * n + a] == Some(b) {
+                return Err(ConfigError {
+                    line: line_no,
+                    message: format!(
+                        "{:?} and {:?} beat each other",
+                        shapes[a].name, shapes[b].name
+                    ),
+                });
             }
+            winner[a * n + b] = Some(a);
+            winner[b * n + a] = Some(a);
+        }
+
+        let mut outcome_symbols = [' '; 3];
+        let mut outcome_scores = [0; 3];
+        for outcome in Outcome::ALL {
+            let (symbol, score) = outcomes[outcome as usize]
+                .ok_or_else(|| error(format!("missing outcome {outcome:?}")))?;
+            outcome_symbols[outcome as usize] = symbol;
+            outcome_scores[outcome as usize] = score;
+        }
+
+        let outcome = |opponent: usize, own: usize| {
+            if opponent == own {
+                Some(Outcome::Draw)
+            } else {
+                winner[opponent * n + own].map(|w| {
+                    if w == own {
+                        Outcome::Win
+                    } else {
+                        Outcome::Lose
+                    }
+                })
+            }
+        };
+
+        let mut scores = vec![0; n * n];
+        let mut choices = vec![0; n * 3];
+        for opponent in 0..n {
+            for own in 0..n {
+                let result = outcome(opponent, own).ok_or_else(|| {
+                    error(format!(
+                        "no winner between {:?} and {:?}",
+                        shapes[opponent].name, shapes[own].name
+                    ))
+                })?;
+                // Widened so that no pair of `u32` scores from the config can overflow.
+                scores[opponent * n + own] =
+                    u64::from(shapes[own].score) + u64::from(outcome_scores[result as usize]);
+            }
+            for desired in Outcome::ALL {
+                choices[opponent * 3 + desired as usize] = (0..n)
+                    .find(|&own| outcome(opponent, own) == Some(desired))
+                    .ok_or_else(|| {
+                        error(format!(
+                            "no shape to {desired:?} against {:?}",
+                            shapes[opponent].name
+                        ))
+                    })?;
+            }
+        }
+
+        Ok(Self {
+            shapes,
+            outcome_symbols,
+            scores,
+            choices,
         })
-        .sum();
-    Some(sum)
+    }
+
+    fn score(&self, opponent: usize, own: usize) -> u64 {
+        self.scores[opponent * self.shapes.len() + own]
+    }
+
+    fn choose(&self, opponent: usize, outcome: Outcome) -> usize {
+        self.choices[opponent * 3 + outcome as usize]
+    }
 }
 
-pub fn part_two(input: &str) -> Option<u32> {
-    let sum = take_lines(input)
-        .map(|(opp, suggestion)| {
-            const LOSE_AGAINST: u8 = b'X';
-            const DRAW_WITH: u8 = b'Y';
-            const WIN_AGAINST: u8 = b'Z';
+#[derive(Debug, PartialEq, Eq)]
+pub struct ParseError {
+    line: usize,
+    message: String,
+}
 
-            match (suggestion, opp) {
-                (DRAW_WITH, ROCK) => 3 + _ROCK,
-                (DRAW_WITH, PAPER) => 3 + _PAPER,
-                (DRAW_WITH, SCISSOR) => 3 + _SCISSOR,
+impl Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for ParseError {}
 
-                (WIN_AGAINST, SCISSOR) => 6 + _ROCK,
-                (WIN_AGAINST, ROCK) => 6 + _PAPER,
-                (WIN_AGAINST, PAPER) => 6 + _SCISSOR,
+/// One line of the strategy guide, resolved against the rules for both readings
+/// of the second column. The rules may define more own shapes than outcomes, so each
+/// reading is only there if the symbol means something in it.
+#[derive(Debug)]
+struct Round {
+    opponent: usize,
+    own: Option<usize>,
+    outcome: Option<Outcome>,
+}
 
-                (LOSE_AGAINST, PAPER) => _ROCK,
-                (LOSE_AGAINST, SCISSOR) => _PAPER,
-                (LOSE_AGAINST, ROCK) => _SCISSOR,
+pub struct StrategyGuide<'a> {
+    rules: &'a Rules,
+    rounds: Vec<Round>,
+}
 
-                _ => unsafe { std::hint::unreachable_unchecked() },
-            }
+fn parse_round(rules: &Rules, line: &str) -> Result<Round, String> {
+    let symbol = |word: &str| {
+        let mut chars = word.chars();
+        chars.next().filter(|_| chars.next().is_none())
+    };
+
+    let mut words = line.split_whitespace();
+    let (Some(opponent), Some(column), None) = (
+        words.next().and_then(symbol),
+        words.next().and_then(symbol),
+        words.next(),
+    ) else {
+        return Err(format!(
+            "expected two symbols separated by whitespace, found {line:?}"
+        ));
+    };
+
+    let opponent = rules
+        .shapes
+        .iter()
+        .position(|shape| shape.opponent_symbol == opponent)
+        .ok_or_else(|| format!("unknown opponent shape {opponent:?}"))?;
+    let own = rules
+        .shapes
+        .iter()
+        .position(|shape| shape.own_symbol == column);
+    let outcome = rules
+        .outcome_symbols
+        .iter()
+        .position(|&symbol| symbol == column)
+        .map(|idx| Outcome::ALL[idx]);
+    if own.is_none() && outcome.is_none() {
+        return Err(format!("unknown own shape or outcome {column:?}"));
+    }
+
+    Ok(Round {
+        opponent,
+        own,
+        outcome,
+    })
+}
+
+pub fn parse<'a>(rules: &'a Rules, input: &str) -> Result<StrategyGuide<'a>, ParseError> {
+    let rounds = input
+        .lines()
+        .zip(1..)
+        .map(|(line, line_no)| {
+            parse_round(rules, line).map_err(|message| ParseError {
+                line: line_no,
+                message,
+            })
         })
-        .sum();
-    Some(sum)
+        .collect::<Result<_, _>>()?;
+
+    Ok(StrategyGuide { rules, rounds })
+}
+
+/// `None` if the second column of a round is not an own shape.
+pub fn part_one(guide: &StrategyGuide) -> Option<u64> {
+    guide
+        .rounds
+        .iter()
+        .map(|round| Some(guide.rules.score(round.opponent, round.own?)))
+        .sum()
+}
+
+/// `None` if the second column of a round is not an outcome.
+pub fn part_two(guide: &StrategyGuide) -> Option<u64> {
+    guide
+        .rounds
+        .iter()
+        .map(|round| {
+            let own = guide.rules.choose(round.opponent, round.outcome?);
+            Some(guide.rules.score(round.opponent, own))
+        })
+        .sum()
 }
 
 fn main() {
+    let mut args = pico_args::Arguments::from_env();
+    let rules = match args.opt_value_from_str::<_, String>("--rules") {
+        Ok(Some(path)) => {
+            let config = std::fs::read_to_string(&path).expect("could not open rules file");
+            advent_of_code::unwrap_or_exit(Rules::from_config(&config))
+        }
+        Ok(None) => Rules::standard(),
+        Err(e) => advent_of_code::unwrap_or_exit(Err(e)),
+    };
+
     let input = &advent_of_code::read_file("inputs", 2);
-    advent_of_code::solve!(1, part_one, input);
-    advent_of_code::solve!(2, part_two, input);
+    let guide =
+        advent_of_code::unwrap_or_exit(advent_of_code::parse!(|input| parse(&rules, input), input));
+    advent_of_code::solve!(1, part_one, &guide);
+    advent_of_code::solve!(2, part_two, &guide);
 }
 
 #[cfg(test)]
@@ -81,12 +380,223 @@ mod tests {
     #[test]
     fn test_part_one() {
         let input = advent_of_code::read_file("examples", 2);
-        assert_eq!(part_one(&input), Some(15));
+        let rules = Rules::standard();
+        assert_eq!(part_one(&parse(&rules, &input).unwrap()), Some(15));
     }
 
     #[test]
     fn test_part_two() {
         let input = advent_of_code::read_file("examples", 2);
-        assert_eq!(part_two(&input), Some(12));
+        let rules = Rules::standard();
+        assert_eq!(part_two(&parse(&rules, &input).unwrap()), Some(12));
+    }
+
+    const RPSLS: &str = "\
+# Rock Paper Scissors Lizard Spock, with the usual scores extended to five shapes.
+shape rock A V 1
+shape paper B W 2
+shape scissors C X 3
+shape lizard D Y 4
+shape spock E Z 5
+beats scissors paper
+beats paper rock
+beats rock lizard
+beats lizard spock
+beats spock scissors
+beats scissors lizard
+beats lizard paper
+beats paper spock
+beats spock rock
+beats rock scissors
+outcome lose X 0
+outcome draw Y 3
+outcome win Z 6
+";
+
+    #[test]
+    fn test_rpsls() {
+        let rules = Rules::from_config(RPSLS).unwrap();
+        let guide = parse(&rules, "E Y\nD Z\nA X\n").unwrap();
+        // Lizard poisons spock, lizard poisons spock, rock crushes scissors.
+        assert_eq!(part_one(&guide), Some(10 + 5 + 3));
+        // Draw with spock, win against lizard with rock, lose against rock with scissors,
+        // the first of the shapes that would lose.
+        assert_eq!(part_two(&guide), Some(8 + 7 + 3));
+    }
+
+    #[test]
+    fn test_rpsls_own_shapes_without_outcome() {
+        let rules = Rules::from_config(RPSLS).unwrap();
+        let guide = parse(
+            &rules,
+            "A V
+E W
+B Z
+",
+        )
+        .unwrap();
+        // Rock draws with rock, paper disproves spock, spock is disproved by paper.
+        assert_eq!(part_one(&guide), Some(4 + 8 + 5));
+        // V and W are only shapes, so the guide can't be read as outcomes.
+        assert_eq!(part_two(&guide), None);
+    }
+
+    #[test]
+    fn test_invalid_rules() {
+        let error = |config: &str| Rules::from_config(config).unwrap_err().to_string();
+        assert_eq!(
+            error("shape rock A X 1\nbeats rock paper\n"),
+            "rules, line 2: unknown shape \"paper\""
+        );
+        assert_eq!(
+            error(&format!("{STANDARD_RULES}beats rock paper\n")),
+            "rules, line 10: \"rock\" and \"paper\" beat each other"
+        );
+        assert_eq!(
+            error(&STANDARD_RULES.replace("outcome win Z 6", "")),
+            "rules, line 9: missing outcome Win"
+        );
+        assert_eq!(
+            error("shape rock A X one\n"),
+            "rules, line 1: expected a score, found \"one\""
+        );
+        assert_eq!(
+            error(&format!("{STANDARD_RULES}outcome win W 6\n")),
+            "rules, line 10: outcome \"win\" is defined twice"
+        );
+        assert_eq!(
+            error("outcome lose X 0\noutcome draw X 3\n"),
+            "rules, line 2: symbol of outcome \"draw\" is already taken"
+        );
+    }
+
+    #[test]
+    fn test_large_scores() {
+        let rules = Rules::from_config(&STANDARD_RULES.replace("A X 1", "A X 4294967295")).unwrap();
+        let guide = parse(&rules, "A X\nA X\nB Y\n").unwrap();
+        assert_eq!(part_one(&guide), Some(2 * (4294967295 + 3) + 5));
+    }
+
+    #[test]
+    fn test_invalid_rounds() {
+        let rules = Rules::standard();
+        let error = |input: &str| parse(&rules, input).err().unwrap().to_string();
+        assert_eq!(error("A X\nD Y\n"), "line 2: unknown opponent shape 'D'");
+        assert_eq!(error("A W\n"), "line 1: unknown own shape or outcome 'W'");
+        assert_eq!(
+            error("A X\nAX\n"),
+            "line 2: expected two symbols separated by whitespace, found \"AX\""
+        );
+        assert_eq!(
+            error("A X Y\n"),
+            "line 1: expected two symbols separated by whitespace, found \"A X Y\""
+        );
+    }
+
+    #[test]
+    fn test_tolerates_whitespace() {
+        let rules = Rules::standard();
+        let input = advent_of_code::normalize_input("A Y \r\nB\tX\r\nC  Z");
+        let guide = parse(&rules, &input).unwrap();
+        assert_eq!(part_one(&guide), Some(15));
+        assert_eq!(part_two(&guide), Some(12));
+    }
+
+    #[test]
+    fn test_matches_reference() {
+        for seed in 0..200 {
+            let input = advent_of_code::generators::day02::generate(seed, seed as usize);
+            let rules = Rules::standard();
+            let guide = parse(&rules, &input).unwrap();
+            assert_eq!(part_one(&guide), reference::part_one(&input), "seed {seed}");
+            assert_eq!(part_two(&guide), reference::part_two(&input), "seed {seed}");
+        }
+    }
+}
+
+/// Slow but obviously correct solutions to check the rules engine against.
+#[cfg(test)]
+mod reference {
+    #[derive(Clone, Copy, PartialEq)]
+    enum Shape {
+        Rock,
+        Paper,
+        Scissors,
+    }
+
+    impl Shape {
+        fn parse(letter: &str) -> Shape {
+            match letter {
+                "A" | "X" => Shape::Rock,
+                "B" | "Y" => Shape::Paper,
+                "C" | "Z" => Shape::Scissors,
+                _ => panic!("Unknown shape {letter}"),
+            }
+        }
+
+        fn beats(self) -> Shape {
+            match self {
+                Shape::Rock => Shape::Scissors,
+                Shape::Paper => Shape::Rock,
+                Shape::Scissors => Shape::Paper,
+            }
+        }
+
+        fn loses_to(self) -> Shape {
+            [Shape::Rock, Shape::Paper, Shape::Scissors]
+                .into_iter()
+                .find(|other| other.beats() == self)
+                .unwrap()
+        }
+
+        fn score(self) -> u64 {
+            match self {
+                Shape::Rock => 1,
+                Shape::Paper => 2,
+                Shape::Scissors => 3,
+            }
+        }
+    }
+
+    fn score(opponent: Shape, me: Shape) -> u64 {
+        let outcome = if me == opponent {
+            3
+        } else if me.beats() == opponent {
+            6
+        } else {
+            0
+        };
+        outcome + me.score()
+    }
+
+    fn rounds(input: &str) -> impl Iterator<Item = (Shape, &str)> {
+        input.lines().map(|line| {
+            let (opponent, column) = line.split_once(' ').unwrap();
+            (Shape::parse(opponent), column)
+        })
+    }
+
+    pub fn part_one(input: &str) -> Option<u64> {
+        Some(
+            rounds(input)
+                .map(|(opponent, me)| score(opponent, Shape::parse(me)))
+                .sum(),
+        )
+    }
+
+    pub fn part_two(input: &str) -> Option<u64> {
+        Some(
+            rounds(input)
+                .map(|(opponent, outcome)| {
+                    let me = match outcome {
+                        "X" => opponent.beats(),
+                        "Y" => opponent,
+                        "Z" => opponent.loses_to(),
+                        _ => panic!("Unknown outcome {outcome}"),
+                    };
+                    score(opponent, me)
+                })
+                .sum(),
+        )
     }
 }
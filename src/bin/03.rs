@@ -1,52 +1,151 @@
-#![feature(iter_array_chunks)]
+use advent_of_code::helpers::ByteSet;
+use std::fmt::Display;
 
-fn bitset(it: &[u8]) -> u64 {
-    it.iter().fold(0_u64, |acc, b| acc | (1 << (b - 64)))
+/// Priorities 1 through 26 for `a` through `z`, 27 through 52 for `A` through `Z`.
+fn priority(item: u8) -> Option<u32> {
+    match item {
+        b'a'..=b'z' => Some((item - b'a') as u32 + 1),
+        b'A'..=b'Z' => Some((item - b'A') as u32 + 27),
+        _ => None,
+    }
 }
 
-fn intersection_priority(bits: u64) -> u32 {
-    let n = bits.trailing_zeros() as u8 + 64;
+fn priority_sum(items: ByteSet) -> u32 {
+    items.iter().filter_map(priority).sum()
+}
 
-    (if n >= b'a' {
-        n - b'a' + 1
-    } else {
-        n - b'A' + 26 + 1
-    } as u32)
+fn chars(items: ByteSet) -> Vec<char> {
+    items.iter().map(char::from).collect()
 }
 
-pub fn part_one(input: &str) -> Option<u32> {
-    Some(
-        input
-            .lines()
-            .map(|line| {
-                let (head, tail) = line.as_bytes().split_at(line.len() / 2);
+#[derive(Debug, PartialEq, Eq)]
+pub enum ErrorKind {
+    UnknownItem(char),
+    OddItemCount(usize),
+}
 
-                let head = bitset(head);
-                let tail = bitset(tail);
+#[derive(Debug, PartialEq, Eq)]
+pub struct ParseError {
+    line: usize,
+    column: usize,
+    kind: ErrorKind,
+}
 
-                intersection_priority(head & tail)
-            })
-            .sum(),
-    )
+impl Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "line {}, column {}: ", self.line, self.column)?;
+        match self.kind {
+            ErrorKind::UnknownItem(ch) => write!(f, "{ch:?} is not an item type"),
+            ErrorKind::OddItemCount(count) => {
+                write!(f, "{count} items cannot be split into two compartments")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+#[derive(Debug)]
+pub struct Rucksack {
+    compartments: [ByteSet; 2],
+}
+
+impl Rucksack {
+    fn parse(line: &str, line_no: usize) -> Result<Self, ParseError> {
+        let error = |column, kind| ParseError {
+            line: line_no,
+            column,
+            kind,
+        };
+
+        if let Some((column, ch)) = line.chars().zip(1..).find_map(|(ch, column)| {
+            (!ch.is_ascii() || priority(ch as u8).is_none()).then_some((column, ch))
+        }) {
+            return Err(error(column, ErrorKind::UnknownItem(ch)));
+        }
+        if !line.len().is_multiple_of(2) {
+            return Err(error(line.len(), ErrorKind::OddItemCount(line.len())));
+        }
+
+        let (head, tail) = line.as_bytes().split_at(line.len() / 2);
+        Ok(Rucksack {
+            compartments: [ByteSet::from_bytes(head), ByteSet::from_bytes(tail)],
+        })
+    }
+
+    fn items(&self) -> ByteSet {
+        self.compartments[0] | self.compartments[1]
+    }
+
+    fn shared(&self) -> ByteSet {
+        self.compartments[0] & self.compartments[1]
+    }
+
+    /// Every item type that is in both compartments.
+    pub fn shared_items(&self) -> Vec<char> {
+        chars(self.shared())
+    }
+}
+
+pub fn parse(input: &str) -> Result<Vec<Rucksack>, ParseError> {
+    input
+        .lines()
+        .zip(1..)
+        .map(|(line, line_no)| Rucksack::parse(line, line_no))
+        .collect()
+}
+
+fn group_badges(
+    rucksacks: &[Rucksack],
+    group_size: usize,
+) -> Option<impl Iterator<Item = ByteSet> + '_> {
+    (group_size > 0 && rucksacks.len().is_multiple_of(group_size)).then(|| {
+        rucksacks.chunks(group_size).map(|group| {
+            group
+                .iter()
+                .fold(ByteSet::ALL, |acc, rucksack| acc & rucksack.items())
+        })
+    })
+}
+
+/// Item types carried by every elf of each group of `group_size` elves, or `None` if the
+/// rucksacks can't be split into whole groups.
+pub fn badges(rucksacks: &[Rucksack], group_size: usize) -> Option<Vec<Vec<char>>> {
+    Some(group_badges(rucksacks, group_size)?.map(chars).collect())
+}
+
+pub fn badge_priorities(rucksacks: &[Rucksack], group_size: usize) -> Option<u32> {
+    Some(group_badges(rucksacks, group_size)?.map(priority_sum).sum())
 }
 
-pub fn part_two(input: &str) -> Option<u32> {
+pub fn part_one(rucksacks: &[Rucksack]) -> Option<u32> {
     Some(
-        input
-            .lines()
-            .array_chunks::<3>()
-            .map(|group| {
-                let [a, b, c] = group.map(str::as_bytes).map(bitset);
-                intersection_priority(a & b & c)
-            })
+        rucksacks
+            .iter()
+            .map(|rucksack| priority_sum(rucksack.shared()))
             .sum(),
     )
 }
 
+pub fn part_two(rucksacks: &[Rucksack]) -> Option<u32> {
+    badge_priorities(rucksacks, 3)
+}
+
 fn main() {
+    let mut args = pico_args::Arguments::from_env();
+    let group_size = advent_of_code::unwrap_or_exit(args.opt_value_from_str("--group-size"));
+
     let input = &advent_of_code::read_file("inputs", 3);
-    advent_of_code::solve!(1, part_one, input);
-    advent_of_code::solve!(2, part_two, input);
+    let rucksacks = advent_of_code::unwrap_or_exit(advent_of_code::parse!(parse, input));
+    advent_of_code::solve!(1, part_one, &rucksacks);
+    match group_size {
+        Some(size) => advent_of_code::solve!(
+            2,
+            |rucksacks: &[Rucksack]| badge_priorities(rucksacks, size),
+            &rucksacks[..]
+        ),
+        None => advent_of_code::solve!(2, part_two, &rucksacks),
+    }
 }
 
 #[cfg(test)]
@@ -56,12 +155,54 @@ mod tests {
     #[test]
     fn test_part_one() {
         let input = advent_of_code::read_file("examples", 3);
-        assert_eq!(part_one(&input), Some(157));
+        assert_eq!(part_one(&parse(&input).unwrap()), Some(157));
     }
 
     #[test]
     fn test_part_two() {
         let input = advent_of_code::read_file("examples", 3);
-        assert_eq!(part_two(&input), Some(70));
+        assert_eq!(part_two(&parse(&input).unwrap()), Some(70));
+    }
+
+    #[test]
+    fn test_all_common_items() {
+        let rucksacks = parse("abcCab\nxyYXyx\nabxa\n").unwrap();
+        assert_eq!(rucksacks[0].shared_items(), ['a', 'b']);
+        assert_eq!(rucksacks[1].shared_items(), ['x', 'y']);
+        assert_eq!(part_one(&rucksacks), Some(1 + 2 + 24 + 25 + 1));
+
+        assert_eq!(badges(&rucksacks, 1).unwrap()[2], ['a', 'b', 'x']);
+        assert_eq!(badges(&rucksacks, 3), Some(vec![vec![]]));
+        assert_eq!(badges(&rucksacks, 2), None);
+        assert_eq!(badge_priorities(&rucksacks, 0), None);
+    }
+
+    #[test]
+    fn test_group_sizes() {
+        let input = advent_of_code::read_file("examples", 3);
+        let rucksacks = parse(&input).unwrap();
+        assert_eq!(badges(&rucksacks, 3), Some(vec![vec!['r'], vec!['Z']]));
+        assert_eq!(badges(&rucksacks, 6), Some(vec![vec![]]));
+        assert_eq!(badges(&rucksacks, 2).unwrap()[2], ['G', 'J', 'Z']);
+        assert_eq!(badges(&rucksacks, 4), None);
+
+        // A group of one elf shares every item type in the rucksack.
+        let singles = badges(&rucksacks, 1).unwrap();
+        assert_eq!(singles.len(), 6);
+        assert_eq!(singles[0], "FJMWcfghprstvw".chars().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_invalid_items() {
+        let error = |input| parse(input).unwrap_err().to_string();
+        assert_eq!(
+            error("abab\nab1b\n"),
+            "line 2, column 3: '1' is not an item type"
+        );
+        assert_eq!(error("abäb\n"), "line 1, column 3: 'ä' is not an item type");
+        assert_eq!(
+            error("aba\n"),
+            "line 1, column 3: 3 items cannot be split into two compartments"
+        );
     }
 }
@@ -1,65 +1,221 @@
-#![feature(slice_split_at_unchecked)]
-use std::{iter::from_fn, ops::RangeInclusive};
+use std::{fmt::Display, str::FromStr};
 
-use memchr::memchr;
+use advent_of_code::helpers::interval::{self, Bound, Interval};
 
-fn split_and_parse(needle: u8, input: &[u8]) -> (u8, &[u8]) {
-    let (head, tail) =
-        unsafe { input.split_at_unchecked(memchr(needle, input).unwrap_unchecked() + 1) };
+pub type Pair<T> = (Interval<T>, Interval<T>);
 
-    let parsed_head = if head.len() == 3 {
-        (head[0] - b'0') * 10 + (head[1] - b'0')
-    } else {
-        head[0] - b'0'
-    };
+#[derive(Debug, PartialEq, Eq)]
+pub enum ErrorKind {
+    ExpectedNumber,
+    NumberOutOfRange,
+    Expected(char),
+    ExpectedEndOfLine,
+    ReversedRange,
+}
 
-    (parsed_head, tail)
+/// Position of a parse error, both line and column are 1-based.
+#[derive(Debug, PartialEq, Eq)]
+pub struct ParseError {
+    line: usize,
+    column: usize,
+    found: Option<char>,
+    kind: ErrorKind,
 }
 
-pub fn pairs(input: &str) -> impl Iterator<Item = (RangeInclusive<u8>, RangeInclusive<u8>)> + '_ {
-    let mut slice = input.as_bytes();
+impl Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "line {}, column {}: ", self.line, self.column)?;
+        match self.kind {
+            ErrorKind::ExpectedNumber => write!(f, "expected a section ID")?,
+            ErrorKind::NumberOutOfRange => write!(f, "section ID is out of range")?,
+            ErrorKind::Expected(ch) => write!(f, "expected {ch:?}")?,
+            ErrorKind::ExpectedEndOfLine => write!(f, "expected end of line")?,
+            ErrorKind::ReversedRange => write!(f, "range ends before it starts")?,
+        }
+        match self.found {
+            Some(ch) => write!(f, ", found {ch:?}"),
+            None => write!(f, ", found end of line"),
+        }
+    }
+}
 
-    from_fn(move || {
-        if slice.is_empty() {
-            return None;
+impl std::error::Error for ParseError {}
+
+struct Cursor<'a> {
+    line: &'a str,
+    line_no: usize,
+    pos: usize,
+}
+
+impl Cursor<'_> {
+    fn error(&self, pos: usize, kind: ErrorKind) -> ParseError {
+        ParseError {
+            line: self.line_no,
+            column: self.line[..pos].chars().count() + 1,
+            found: self.line[pos..].chars().next(),
+            kind,
         }
+    }
 
-        let (first_elf_start, tail) = split_and_parse(b'-', slice);
-        let (first_elf_end, tail) = split_and_parse(b',', tail);
-        let (second_elf_start, tail) = split_and_parse(b'-', tail);
-        let (second_elf_end, tail) = split_and_parse(b'\n', tail);
-        slice = tail;
+    fn number<T: FromStr>(&mut self) -> Result<T, ParseError> {
+        let start = self.pos;
+        let len = self.line[start..]
+            .bytes()
+            .take_while(u8::is_ascii_digit)
+            .count();
+        if len == 0 {
+            return Err(self.error(start, ErrorKind::ExpectedNumber));
+        }
+        self.pos += len;
+        self.line[start..self.pos]
+            .parse()
+            .map_err(|_| self.error(start, ErrorKind::NumberOutOfRange))
+    }
+
+    fn expect(&mut self, ch: char) -> Result<(), ParseError> {
+        if self.line[self.pos..].starts_with(ch) {
+            self.pos += ch.len_utf8();
+            Ok(())
+        } else {
+            Err(self.error(self.pos, ErrorKind::Expected(ch)))
+        }
+    }
+
+    fn end(&self) -> Result<(), ParseError> {
+        if self.pos == self.line.len() {
+            Ok(())
+        } else {
+            Err(self.error(self.pos, ErrorKind::ExpectedEndOfLine))
+        }
+    }
 
-        Some((
-            first_elf_start..=first_elf_end,
-            second_elf_start..=second_elf_end,
-        ))
-    })
+    fn range<T: FromStr + Bound>(&mut self) -> Result<Interval<T>, ParseError> {
+        let range_start = self.pos;
+        let start = self.number()?;
+        self.expect('-')?;
+        let end = self.number()?;
+        if start > end {
+            return Err(self.error(range_start, ErrorKind::ReversedRange));
+        }
+        Ok(Interval::new(start, end))
+    }
 }
 
-pub fn part_one(input: &str) -> Option<u32> {
-    Some(
-        pairs(input)
-            .filter(|(a, b)| {
-                a.contains(b.start()) & a.contains(b.end())
-                    | b.contains(a.start()) & b.contains(a.end())
-            })
-            .count() as u32,
-    )
+fn parse_line<T: FromStr + Bound>(line: &str, line_no: usize) -> Result<Pair<T>, ParseError> {
+    let mut cursor = Cursor {
+        line,
+        line_no,
+        pos: 0,
+    };
+    let first = cursor.range()?;
+    cursor.expect(',')?;
+    let second = cursor.range()?;
+    cursor.end()?;
+    Ok((first, second))
+}
+
+/// Parses one pair of section assignments per line, with section IDs of any width that fits `T`.
+/// Accepts `\n` and `\r\n` line endings, with or without a final line ending.
+pub fn pairs<T: FromStr + Bound>(
+    input: &str,
+) -> impl Iterator<Item = Result<Pair<T>, ParseError>> + '_ {
+    input
+        .lines()
+        .zip(1..)
+        .map(|(line, line_no)| parse_line(line, line_no))
 }
 
-pub fn part_two(input: &str) -> Option<u32> {
+/// Fast parser for well-formed input only: one- or two-digit section IDs and every line
+/// terminated by `\n`. Anything else is undefined behavior.
+#[cfg(feature = "unchecked")]
+mod unchecked {
+    use super::Pair;
+    use advent_of_code::helpers::Interval;
+    use memchr::memchr;
+    use std::iter::from_fn;
+
+    fn split_and_parse(needle: u8, input: &[u8]) -> (u8, &[u8]) {
+        let (head, tail) =
+            unsafe { input.split_at_unchecked(memchr(needle, input).unwrap_unchecked() + 1) };
+
+        let parsed_head = if head.len() == 3 {
+            (head[0] - b'0') * 10 + (head[1] - b'0')
+        } else {
+            head[0] - b'0'
+        };
+
+        (parsed_head, tail)
+    }
+
+    pub fn pairs(input: &str) -> impl Iterator<Item = Pair<u32>> + '_ {
+        let mut slice = input.as_bytes();
+
+        from_fn(move || {
+            if slice.is_empty() {
+                return None;
+            }
+
+            let (first_elf_start, tail) = split_and_parse(b'-', slice);
+            let (first_elf_end, tail) = split_and_parse(b',', tail);
+            let (second_elf_start, tail) = split_and_parse(b'-', tail);
+            let (second_elf_end, tail) = split_and_parse(b'\n', tail);
+            slice = tail;
+
+            Some((
+                Interval::new(first_elf_start.into(), first_elf_end.into()),
+                Interval::new(second_elf_start.into(), second_elf_end.into()),
+            ))
+        })
+    }
+}
+
+/// Uses the checked parser unless the `unchecked` feature opts into the fast path.
+pub fn parse(input: &str) -> Result<Vec<Pair<u32>>, ParseError> {
+    #[cfg(feature = "unchecked")]
+    return Ok(unchecked::pairs(input).collect());
+
+    #[cfg(not(feature = "unchecked"))]
+    pairs(input).collect()
+}
+
+pub fn part_one(pairs: &[Pair<u32>]) -> Option<u32> {
     Some(
-        pairs(input)
-            .filter(|(a, b)| a.start().max(b.start()) <= a.end().min(b.end()))
+        pairs
+            .iter()
+            .filter(|(a, b)| a.contains_interval(b) || b.contains_interval(a))
             .count() as u32,
     )
 }
 
+pub fn part_two(pairs: &[Pair<u32>]) -> Option<u32> {
+    Some(pairs.iter().filter(|(a, b)| a.overlaps(b)).count() as u32)
+}
+
+fn assignments(pairs: &[Pair<u32>]) -> impl Iterator<Item = Interval<u32>> + '_ {
+    pairs.iter().flat_map(|&(a, b)| [a, b])
+}
+
+/// Number of sections assigned to at least one elf.
+pub fn sections_covered(pairs: &[Pair<u32>]) -> u32 {
+    interval::coverage(assignments(pairs))
+}
+
+/// Sections assigned to more than one elf, across all pairs.
+pub fn sections_shared(pairs: &[Pair<u32>]) -> Vec<Interval<u32>> {
+    interval::covered_at_least(assignments(pairs), 2)
+}
+
 fn main() {
     let input = &advent_of_code::read_file("inputs", 4);
-    advent_of_code::solve!(1, part_one, input);
-    advent_of_code::solve!(2, part_two, input);
+    let pairs = advent_of_code::unwrap_or_exit(advent_of_code::parse!(parse, input));
+    advent_of_code::solve!(1, part_one, &pairs);
+    advent_of_code::solve!(2, part_two, &pairs);
+
+    println!("Sections covered: {}", sections_covered(&pairs));
+    println!(
+        "Sections shared: {}",
+        interval::coverage(sections_shared(&pairs))
+    );
 }
 
 #[cfg(test)]
@@ -69,12 +225,140 @@ mod tests {
     #[test]
     fn test_part_one() {
         let input = advent_of_code::read_file("examples", 4);
-        assert_eq!(part_one(&input), Some(2));
+        assert_eq!(part_one(&parse(&input).unwrap()), Some(2));
     }
 
     #[test]
     fn test_part_two() {
         let input = advent_of_code::read_file("examples", 4);
-        assert_eq!(part_two(&input), None);
+        assert_eq!(part_two(&parse(&input).unwrap()), Some(4));
+    }
+
+    #[test]
+    fn test_tolerates_line_endings() {
+        let pairs = |input| pairs::<u32>(input).collect::<Result<Vec<_>, _>>().unwrap();
+        let expected = vec![
+            (Interval::new(2, 4), Interval::new(6, 8)),
+            (Interval::new(2, 3), Interval::new(4, 5)),
+        ];
+        assert_eq!(pairs("2-4,6-8\n2-3,4-5\n"), expected);
+        assert_eq!(pairs("2-4,6-8\n2-3,4-5"), expected);
+        assert_eq!(pairs("2-4,6-8\r\n2-3,4-5\r\n"), expected);
+    }
+
+    #[test]
+    fn test_wide_section_ids() {
+        let parsed: Vec<Pair<u64>> = pairs("100-4294967296,7-12345\n")
+            .map(Result::unwrap)
+            .collect();
+        assert_eq!(
+            parsed,
+            vec![(Interval::new(100, 4294967296), Interval::new(7, 12345))]
+        );
+
+        let err = pairs::<u8>("2-4,6-8\n1-256,3-3\n")
+            .nth(1)
+            .unwrap()
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "line 2, column 3: section ID is out of range, found '2'"
+        );
+    }
+
+    #[test]
+    fn test_reports_position() {
+        let error = |input| {
+            pairs::<u32>(input)
+                .find_map(Result::err)
+                .unwrap()
+                .to_string()
+        };
+        assert_eq!(
+            error("2-4,6-8\n2-3;4-5\n"),
+            "line 2, column 4: expected ',', found ';'"
+        );
+        assert_eq!(
+            error("2-4,6-8\n\n"),
+            "line 2, column 1: expected a section ID, found end of line"
+        );
+        assert_eq!(
+            error("2-4,6-"),
+            "line 1, column 7: expected a section ID, found end of line"
+        );
+        assert_eq!(
+            error("2-4,6-8 \n"),
+            "line 1, column 8: expected end of line, found ' '"
+        );
+        assert_eq!(
+            error("2-4,8-6\n"),
+            "line 1, column 5: range ends before it starts, found '8'"
+        );
+    }
+
+    #[test]
+    fn test_sections() {
+        let input = advent_of_code::read_file("examples", 4);
+        let pairs = parse(&input).unwrap();
+        assert_eq!(sections_covered(&pairs), 8);
+        assert_eq!(sections_shared(&pairs), vec![Interval::new(2, 8)]);
+    }
+
+    #[test]
+    fn test_matches_reference() {
+        for seed in 0..200 {
+            let input = advent_of_code::generators::day04::generate(seed, seed as usize);
+            let pairs = parse(&input).unwrap();
+            assert_eq!(part_one(&pairs), reference::part_one(&input), "seed {seed}");
+            assert_eq!(part_two(&pairs), reference::part_two(&input), "seed {seed}");
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "unchecked")]
+    fn test_unchecked_matches_checked() {
+        for seed in 0..200 {
+            let input = advent_of_code::generators::day04::generate(seed, seed as usize);
+            let checked: Vec<_> = pairs(&input).collect::<Result<_, _>>().unwrap();
+            let fast: Vec<_> = unchecked::pairs(&input).collect();
+            assert_eq!(fast, checked, "seed {seed}");
+        }
+    }
+}
+
+/// Slow but obviously correct solutions to check the solutions against, with either parser.
+#[cfg(test)]
+mod reference {
+    fn pairs(input: &str) -> Vec<((u32, u32), (u32, u32))> {
+        let range = |s: &str| {
+            let (start, end) = s.split_once('-').unwrap();
+            (start.parse().unwrap(), end.parse().unwrap())
+        };
+
+        input
+            .lines()
+            .map(|line| {
+                let (a, b) = line.split_once(',').unwrap();
+                (range(a), range(b))
+            })
+            .collect()
+    }
+
+    pub fn part_one(input: &str) -> Option<u32> {
+        let count = pairs(input)
+            .into_iter()
+            .filter(|&((a_start, a_end), (b_start, b_end))| {
+                (a_start <= b_start && b_end <= a_end) || (b_start <= a_start && a_end <= b_end)
+            })
+            .count();
+        Some(count as u32)
+    }
+
+    pub fn part_two(input: &str) -> Option<u32> {
+        let count = pairs(input)
+            .into_iter()
+            .filter(|&((a_start, a_end), (b_start, b_end))| a_start <= b_end && b_start <= a_end)
+            .count();
+        Some(count as u32)
     }
 }
@@ -1,26 +1,132 @@
-#![feature(iter_advance_by)]
-#![feature(assert_matches)]
-#![feature(get_many_mut)]
+use advent_of_code::generators::day05;
+use std::{
+    fmt::Display,
+    num::NonZeroUsize,
+    ops::{Add, AddAssign},
+    thread,
+    time::Duration,
+};
 
-use std::iter::from_fn;
-
-#[derive(Debug)]
-struct Stacks {
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Stacks {
     stacks: Vec<Vec<char>>,
 }
 
+/// How a crane moves crates: every lift picks up the top crates of the source stack and
+/// puts them onto the destination stack in the same order.
+pub trait Crane {
+    /// Number of crates to pick up when `remaining` crates of a move are still to be moved.
+    /// Clamped to `1..=remaining`.
+    fn lift_size(&self, remaining: usize) -> usize;
+}
+
+/// Moves one crate at a time, which reverses the order of the moved crates.
+pub struct CrateMover9000;
+
+impl Crane for CrateMover9000 {
+    fn lift_size(&self, _remaining: usize) -> usize {
+        1
+    }
+}
+
+/// Moves all crates of a move at once, which keeps their order.
+pub struct CrateMover9001;
+
+impl Crane for CrateMover9001 {
+    fn lift_size(&self, remaining: usize) -> usize {
+        remaining
+    }
+}
+
+/// Moves up to `capacity` crates at once.
+pub struct BoundedCrane {
+    capacity: usize,
+}
+
+impl BoundedCrane {
+    /// Panics if `capacity` is zero.
+    pub fn new(capacity: usize) -> Self {
+        assert!(
+            capacity > 0,
+            "Crane must be able to lift at least one crate"
+        );
+        Self { capacity }
+    }
+}
+
+impl Crane for BoundedCrane {
+    fn lift_size(&self, remaining: usize) -> usize {
+        self.capacity.min(remaining)
+    }
+}
+
+/// Work done by a crane.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Lifts {
+    pub lifts: usize,
+    pub crates: usize,
+}
+
+impl Lifts {
+    /// Total cost when every lift costs `per_lift` plus `per_crate` for each crate lifted.
+    pub fn cost(&self, per_lift: u64, per_crate: u64) -> u64 {
+        self.lifts as u64 * per_lift + self.crates as u64 * per_crate
+    }
+}
+
+impl Add for Lifts {
+    type Output = Lifts;
+
+    fn add(mut self, rhs: Self) -> Self::Output {
+        self += rhs;
+        self
+    }
+}
+
+impl AddAssign for Lifts {
+    fn add_assign(&mut self, rhs: Self) {
+        self.lifts += rhs.lifts;
+        self.crates += rhs.crates;
+    }
+}
+
+/// Sizes of the lifts `crane` makes to move `count` crates, in the order it makes them.
+fn lift_sizes(count: usize, crane: &(impl Crane + ?Sized)) -> impl Iterator<Item = usize> + '_ {
+    let mut remaining = count;
+    std::iter::from_fn(move || {
+        (remaining > 0).then(|| {
+            let size = crane.lift_size(remaining).clamp(1, remaining);
+            remaining -= size;
+            size
+        })
+    })
+}
+
 impl Stacks {
-    fn perform(&mut self, m: Move, rev: bool) {
-        let [source, dest] = self.stacks.get_many_mut([m.from, m.to]).unwrap();
+    fn perform(&mut self, m: &Move, crane: &(impl Crane + ?Sized)) -> Lifts {
+        let [source, dest] = self.stacks.get_disjoint_mut([m.from, m.to]).unwrap();
+        let mut lifts = Lifts::default();
 
-        let remove_from = source.len() - m.count;
-        let drain = source.drain(remove_from..);
+        for size in lift_sizes(m.count, crane) {
+            dest.extend(source.drain(source.len() - size..));
+            lifts += Lifts {
+                lifts: 1,
+                crates: size,
+            };
+        }
 
-        if rev {
-            dest.extend(drain.rev())
-        } else {
-            dest.extend(drain)
-        };
+        lifts
+    }
+
+    /// Reverts [`Stacks::perform`] by making the same lifts in reverse order, back onto the
+    /// source stack. The destination stack must hold at least `m.count` crates.
+    fn undo(&mut self, m: &Move, crane: &(impl Crane + ?Sized)) {
+        let sizes: Vec<_> = lift_sizes(m.count, crane).collect();
+        let [source, dest] = self.stacks.get_disjoint_mut([m.from, m.to]).unwrap();
+
+        for size in sizes.into_iter().rev() {
+            source.extend(dest.drain(dest.len() - size..));
+        }
     }
 
     pub fn message(&self) -> String {
@@ -28,79 +134,602 @@ impl Stacks {
     }
 }
 
+/// Renders the stacks in the format of the puzzle's drawing, labels row included.
+impl Display for Stacks {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&day05::render_drawing(&self.stacks))
+    }
+}
+
 #[derive(Debug)]
-struct Move {
+pub struct Move {
     from: usize,
     to: usize,
     count: usize,
 }
 
-fn parse_input(input: &str) -> (Stacks, impl Iterator<Item = Move> + '_) {
-    let mut chars = input.chars();
+impl Display for Move {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "move {} from {} to {}",
+            self.count,
+            self.from + 1,
+            self.to + 1
+        )
+    }
+}
+
+/// Applies moves one at a time and yields each move with the state of the stacks after it.
+pub struct Simulation<'a> {
+    stacks: Stacks,
+    moves: std::slice::Iter<'a, Move>,
+    crane: &'a dyn Crane,
+}
+
+impl<'a> Simulation<'a> {
+    pub fn new(stacks: &Stacks, moves: &'a [Move], crane: &'a dyn Crane) -> Self {
+        Self {
+            stacks: stacks.clone(),
+            moves: moves.iter(),
+            crane,
+        }
+    }
+}
 
-    let num = (memchr::memchr(b'\n', input.as_bytes()).unwrap() + 1) / 4;
+impl<'a> Iterator for Simulation<'a> {
+    type Item = (&'a Move, Stacks);
 
-    let mut stacks = vec![Vec::new(); num];
+    fn next(&mut self) -> Option<Self::Item> {
+        let m = self.moves.next()?;
+        self.stacks.perform(m, self.crane);
+        Some((m, self.stacks.clone()))
+    }
+}
 
-    chars
-        .by_ref()
-        .enumerate()
-        .skip(1)
-        .step_by(2)
-        .take_while(|&(_, ch)| ch != 'm')
-        .filter(|(_, ch)| ch.is_ascii_uppercase())
-        .for_each(|(pos, ch)| {
-            unsafe { stacks.get_unchecked_mut((pos / 4) % num) }.push(ch);
+/// Every state of the stacks, from the drawing to the state after the last move.
+pub fn trace(stacks: &Stacks, moves: &[Move], crane: &dyn Crane) -> Vec<Stacks> {
+    std::iter::once(stacks.clone())
+        .chain(Simulation::new(stacks, moves, crane).map(|(_, state)| state))
+        .collect()
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum ErrorKind {
+    MissingLabels,
+    UnexpectedLabel { expected: usize, found: String },
+    Unexpected { column: usize, found: Option<char> },
+    UnlabeledCrate { column: usize },
+    FloatingCrate { stack: usize },
+    InvalidMove,
+    NoSuchStack(usize),
+    SameStack(usize),
+    NotEnoughCrates { stack: usize, available: usize },
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct ParseError {
+    line: usize,
+    kind: ErrorKind,
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "line {}: ", self.line)?;
+        match &self.kind {
+            ErrorKind::MissingLabels => write!(f, "expected a row of stack numbers"),
+            ErrorKind::UnexpectedLabel { expected, found } => {
+                write!(f, "expected stack number {expected}, found {found:?}")
+            }
+            ErrorKind::Unexpected { column, found } => {
+                write!(f, "column {column}: unexpected ")?;
+                match found {
+                    Some(ch) => write!(f, "{ch:?}"),
+                    None => write!(f, "end of line"),
+                }
+            }
+            ErrorKind::UnlabeledCrate { column } => {
+                write!(f, "column {column}: crate is not above a stack number")
+            }
+            ErrorKind::FloatingCrate { stack } => {
+                write!(f, "crate on stack {stack} is not resting on another crate")
+            }
+            ErrorKind::InvalidMove => write!(f, "expected \"move <n> from <stack> to <stack>\""),
+            ErrorKind::NoSuchStack(stack) => write!(f, "there is no stack {stack}"),
+            ErrorKind::SameStack(stack) => write!(f, "move from stack {stack} onto itself"),
+            ErrorKind::NotEnoughCrates { stack, available } => {
+                write!(f, "stack {stack} only has {available} crates")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// Stack numbers of the label row and the columns they span, as `(start, end)` char indices.
+fn parse_labels(line: &str, line_no: usize) -> Result<Vec<(usize, usize)>, ParseError> {
+    let chars: Vec<char> = line.chars().collect();
+    let mut spans = vec![];
+    let mut pos = 0;
+
+    while pos < chars.len() {
+        if chars[pos] == ' ' {
+            pos += 1;
+            continue;
+        }
+        let start = pos;
+        while pos < chars.len() && chars[pos] != ' ' {
+            pos += 1;
+        }
+        let label: String = chars[start..pos].iter().collect();
+        let expected = spans.len() + 1;
+        if label.parse() != Ok(expected) {
+            return Err(ParseError {
+                line: line_no,
+                kind: ErrorKind::UnexpectedLabel {
+                    expected,
+                    found: label,
+                },
+            });
+        }
+        spans.push((start, pos));
+    }
+
+    Ok(spans)
+}
+
+/// Crates of one row of the drawing as `(stack index, crate)`.
+fn parse_row(
+    line: &str,
+    line_no: usize,
+    labels: &[(usize, usize)],
+) -> Result<Vec<(usize, char)>, ParseError> {
+    let error = |kind| ParseError {
+        line: line_no,
+        kind,
+    };
+    let chars: Vec<char> = line.chars().collect();
+    let mut crates = vec![];
+    let mut pos = 0;
+
+    while pos < chars.len() {
+        if chars[pos] == ' ' {
+            pos += 1;
+            continue;
+        }
+        for (offset, expected) in [(0, Some('[')), (1, None), (2, Some(']'))] {
+            let found = chars.get(pos + offset).copied();
+            let valid = match (expected, found) {
+                (Some(expected), Some(found)) => expected == found,
+                (None, Some(found)) => !matches!(found, ' ' | '[' | ']'),
+                (_, None) => false,
+            };
+            if !valid {
+                return Err(error(ErrorKind::Unexpected {
+                    column: pos + offset + 1,
+                    found,
+                }));
+            }
+        }
+
+        let column = pos + 1;
+        let stack = labels
+            .iter()
+            .position(|&(start, end)| (start..end).contains(&column))
+            .ok_or_else(|| error(ErrorKind::UnlabeledCrate { column: column + 1 }))?;
+        crates.push((stack, chars[column]));
+        pos += 3;
+    }
+
+    Ok(crates)
+}
+
+fn parse_drawing(lines: &[&str]) -> Result<Stacks, ParseError> {
+    let (&labels, rows) = lines.split_last().ok_or(ParseError {
+        line: 1,
+        kind: ErrorKind::MissingLabels,
+    })?;
+    let labels = parse_labels(labels, lines.len())?;
+    if labels.is_empty() {
+        return Err(ParseError {
+            line: lines.len(),
+            kind: ErrorKind::MissingLabels,
         });
+    }
 
-    for stack in stacks.iter_mut() {
-        stack.reverse();
+    let mut stacks = vec![Vec::new(); labels.len()];
+    for (level, (idx, row)) in rows.iter().enumerate().rev().enumerate() {
+        let line_no = idx + 1;
+        for (stack, label) in parse_row(row, line_no, &labels)? {
+            if stacks[stack].len() != level {
+                return Err(ParseError {
+                    line: line_no,
+                    kind: ErrorKind::FloatingCrate { stack: stack + 1 },
+                });
+            }
+            stacks[stack].push(label);
+        }
     }
 
-    let moves = from_fn(move || {
-        chars.next()?;
-        let mut get_num = || {
-            chars
-                .by_ref()
-                .skip_while(|ch| !ch.is_ascii_digit())
-                .take_while(|ch| ch.is_ascii_digit())
-                .fold(0, |acc, ch| acc * 10 + (ch as u8 - b'0')) as usize
-        };
+    Ok(Stacks { stacks })
+}
+
+fn parse_move(line: &str, line_no: usize, stack_count: usize) -> Result<Move, ParseError> {
+    let error = |kind| ParseError {
+        line: line_no,
+        kind,
+    };
+    let number = |word: Option<&str>| -> Result<usize, ParseError> {
+        word.and_then(|word| word.parse().ok())
+            .ok_or_else(|| error(ErrorKind::InvalidMove))
+    };
+    let keyword = |word: Option<&str>, expected| {
+        (word == Some(expected))
+            .then_some(())
+            .ok_or_else(|| error(ErrorKind::InvalidMove))
+    };
 
-        let count = get_num();
-        let from = get_num() - 1;
-        let to = get_num() - 1;
+    let mut words = line.split_whitespace();
+    keyword(words.next(), "move")?;
+    let count = number(words.next())?;
+    keyword(words.next(), "from")?;
+    let from = number(words.next())?;
+    keyword(words.next(), "to")?;
+    let to = number(words.next())?;
+    if words.next().is_some() {
+        return Err(error(ErrorKind::InvalidMove));
+    }
 
-        Some(Move { from, to, count })
-    });
+    for stack in [from, to] {
+        if !(1..=stack_count).contains(&stack) {
+            return Err(error(ErrorKind::NoSuchStack(stack)));
+        }
+    }
+    if from == to {
+        return Err(error(ErrorKind::SameStack(from)));
+    }
 
-    (Stacks { stacks }, moves)
+    Ok(Move {
+        from: from - 1,
+        to: to - 1,
+        count,
+    })
 }
 
-pub fn part_one(input: &str) -> Option<String> {
-    let (mut stacks, moves) = parse_input(input);
+/// Keeps track of the height of each stack, to check that there are enough crates for `m`.
+fn check_heights(m: &Move, line_no: usize, heights: &mut [usize]) -> Result<(), ParseError> {
+    if heights[m.from] < m.count {
+        return Err(ParseError {
+            line: line_no,
+            kind: ErrorKind::NotEnoughCrates {
+                stack: m.from + 1,
+                available: heights[m.from],
+            },
+        });
+    }
+    heights[m.from] -= m.count;
+    heights[m.to] += m.count;
+    Ok(())
+}
 
-    moves.for_each(|m| {
-        stacks.perform(m, true);
-    });
+/// Splits the lines at the first blank line into the drawing and the numbered move lines.
+fn split_drawing<'a, 'b>(
+    lines: &'b [&'a str],
+) -> (&'b [&'a str], impl Iterator<Item = (&'a str, usize)> + 'b) {
+    let drawing_len = lines
+        .iter()
+        .position(|line| line.trim().is_empty())
+        .unwrap_or(lines.len());
+    let moves = lines
+        .iter()
+        .copied()
+        .zip(1..)
+        .skip(drawing_len + 1)
+        .filter(|(line, _)| !line.trim().is_empty());
+    (&lines[..drawing_len], moves)
+}
 
-    Some(stacks.message())
+/// Parses the drawing, whose columns are determined by the row of stack numbers below it,
+/// and the moves after the first blank line. Every move is checked against the stacks as they
+/// are when it is performed, so the parts can't fail on the result.
+pub fn parse(input: &str) -> Result<(Stacks, Vec<Move>), ParseError> {
+    let lines: Vec<&str> = input.lines().collect();
+    let (drawing, moves) = split_drawing(&lines);
+
+    let stacks = parse_drawing(drawing)?;
+    let mut heights: Vec<usize> = stacks.stacks.iter().map(Vec::len).collect();
+    let moves = moves
+        .map(|(line, line_no)| {
+            let m = parse_move(line, line_no, heights.len())?;
+            check_heights(&m, line_no, &mut heights)?;
+            Ok(m)
+        })
+        .collect::<Result<_, _>>()?;
+
+    Ok((stacks, moves))
 }
 
-pub fn part_two(input: &str) -> Option<String> {
-    let (mut stacks, moves) = parse_input(input);
+/// Parses a drawing of the stacks after all moves, followed by the moves that led to it.
+/// Heights are only checked by [`unwind`], since undoing moves starts from the end.
+pub fn parse_backwards(input: &str) -> Result<(Stacks, Vec<Move>), ParseError> {
+    let lines: Vec<&str> = input.lines().collect();
+    let (drawing, moves) = split_drawing(&lines);
+
+    let stacks = parse_drawing(drawing)?;
+    let moves = moves
+        .map(|(line, line_no)| parse_move(line, line_no, stacks.stacks.len()))
+        .collect::<Result<_, _>>()?;
 
-    moves.for_each(|m| {
-        stacks.perform(m, false);
-    });
+    Ok((stacks, moves))
+}
 
-    Some(stacks.message())
+/// Parses a drawing without moves.
+pub fn parse_stacks(input: &str) -> Result<Stacks, ParseError> {
+    let lines: Vec<&str> = input.lines().collect();
+    let (drawing, _) = split_drawing(&lines);
+    parse_drawing(drawing)
+}
+
+/// Fast parser for well-formed input only: at most nine stacks, uppercase crates and a
+/// drawing without trimmed trailing spaces. Anything else is undefined behavior.
+#[cfg(feature = "unchecked")]
+mod unchecked {
+    use super::{Move, Stacks};
+    use std::iter::from_fn;
+
+    pub fn parse_input(input: &str) -> (Stacks, Vec<Move>) {
+        let mut chars = input.chars();
+
+        let num = (memchr::memchr(b'\n', input.as_bytes()).unwrap() + 1) / 4;
+
+        let mut stacks = vec![Vec::new(); num];
+
+        chars
+            .by_ref()
+            .enumerate()
+            .skip(1)
+            .step_by(2)
+            .take_while(|&(_, ch)| ch != 'm')
+            .filter(|(_, ch)| ch.is_ascii_uppercase())
+            .for_each(|(pos, ch)| {
+                unsafe { stacks.get_unchecked_mut((pos / 4) % num) }.push(ch);
+            });
+
+        for stack in stacks.iter_mut() {
+            stack.reverse();
+        }
+
+        let moves = from_fn(move || {
+            chars.next()?;
+            let mut get_num = || {
+                chars
+                    .by_ref()
+                    .skip_while(|ch| !ch.is_ascii_digit())
+                    .take_while(|ch| ch.is_ascii_digit())
+                    .fold(0, |acc, ch| acc * 10 + (ch as u8 - b'0')) as usize
+            };
+
+            let count = get_num();
+            let from = get_num() - 1;
+            let to = get_num() - 1;
+
+            Some(Move { from, to, count })
+        });
+
+        (Stacks { stacks }, moves.collect())
+    }
+}
+
+/// Uses the validating parser unless the `unchecked` feature opts into the fast path.
+pub fn parse_input(input: &str) -> Result<(Stacks, Vec<Move>), ParseError> {
+    #[cfg(feature = "unchecked")]
+    return Ok(unchecked::parse_input(input));
+
+    #[cfg(not(feature = "unchecked"))]
+    parse(input)
+}
+
+/// Final stacks and the work done after performing all moves with `crane`.
+pub fn operate(stacks: &Stacks, moves: &[Move], crane: &(impl Crane + ?Sized)) -> (Stacks, Lifts) {
+    let mut stacks = stacks.clone();
+    let lifts = moves
+        .iter()
+        .map(|m| stacks.perform(m, crane))
+        .fold(Lifts::default(), Add::add);
+    (stacks, lifts)
+}
+
+pub fn part_one((stacks, moves): &(Stacks, Vec<Move>)) -> Option<String> {
+    Some(operate(stacks, moves, &CrateMover9000).0.message())
+}
+
+pub fn part_two((stacks, moves): &(Stacks, Vec<Move>)) -> Option<String> {
+    Some(operate(stacks, moves, &CrateMover9001).0.message())
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum UndoError {
+    NoSuchStack {
+        step: usize,
+        stack: usize,
+    },
+    NotEnoughCrates {
+        step: usize,
+        stack: usize,
+        available: usize,
+    },
+}
+
+impl Display for UndoError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            UndoError::NoSuchStack { step, stack } => {
+                write!(f, "cannot undo move {step}: there is no stack {stack}")
+            }
+            UndoError::NotEnoughCrates {
+                step,
+                stack,
+                available,
+            } => write!(
+                f,
+                "cannot undo move {step}: stack {stack} only has {available} crates"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for UndoError {}
+
+/// Reconstructs the stacks before `moves` were performed with `crane`, given the stacks after.
+pub fn unwind(
+    end: &Stacks,
+    moves: &[Move],
+    crane: &(impl Crane + ?Sized),
+) -> Result<Stacks, UndoError> {
+    let mut stacks = end.clone();
+
+    for (idx, m) in moves.iter().enumerate().rev() {
+        let step = idx + 1;
+        let Some(available) = stacks.stacks.get(m.to).map(Vec::len) else {
+            return Err(UndoError::NoSuchStack {
+                step,
+                stack: m.to + 1,
+            });
+        };
+        if m.from >= stacks.stacks.len() {
+            return Err(UndoError::NoSuchStack {
+                step,
+                stack: m.from + 1,
+            });
+        }
+        if available < m.count {
+            return Err(UndoError::NotEnoughCrates {
+                step,
+                stack: m.to + 1,
+                available,
+            });
+        }
+        stacks.undo(m, crane);
+    }
+
+    Ok(stacks)
+}
+
+/// Whether performing `moves` with `crane` on `start` results in `end`. Checked by undoing the
+/// moves, so moves that `start` doesn't have enough crates for fail instead of panicking.
+pub fn verify(start: &Stacks, moves: &[Move], end: &Stacks, crane: &(impl Crane + ?Sized)) -> bool {
+    start.stacks.len() == end.stacks.len()
+        && moves
+            .iter()
+            .all(|m| m.from < start.stacks.len() && m.to < start.stacks.len())
+        && unwind(end, moves, crane).as_ref() == Ok(start)
+}
+
+/// Puzzle input for the drawing of `stacks` followed by `moves`.
+pub fn render_input(stacks: &Stacks, moves: &[Move]) -> String {
+    let moves: Vec<_> = moves.iter().map(Move::to_string).collect();
+    format!("{stacks}\n{}\n", moves.join("\n"))
+}
+
+fn show_simulation(
+    (stacks, moves): &(Stacks, Vec<Move>),
+    crane: &dyn Crane,
+    delay: Option<Duration>,
+) {
+    const CLEAR_SCREEN: &str = "\x1b[2J\x1b[H";
+
+    let steps = std::iter::once((None, stacks.clone()))
+        .chain(Simulation::new(stacks, moves, crane).map(|(m, state)| (Some(m), state)));
+    for (step, (m, state)) in steps.enumerate() {
+        if delay.is_some() {
+            print!("{CLEAR_SCREEN}");
+        }
+        match m {
+            Some(m) => println!("Step {step}/{}: {m}", moves.len()),
+            None => println!("Drawing"),
+        }
+        println!("{state}");
+        if let Some(delay) = delay {
+            thread::sleep(delay);
+        }
+    }
+}
+
+fn read_drawing(path: &str) -> String {
+    advent_of_code::normalize_input(&advent_of_code::unwrap_or_exit(std::fs::read_to_string(
+        path,
+    )))
 }
 
 fn main() {
+    let mut args = pico_args::Arguments::from_env();
+    let trace = args.contains("--trace");
+    let animate = args.contains("--animate");
+    let part: u8 = advent_of_code::unwrap_or_exit(args.opt_value_from_str("--part")).unwrap_or(1);
+    let capacity: Option<NonZeroUsize> =
+        advent_of_code::unwrap_or_exit(args.opt_value_from_str("--capacity"));
+    let bounded = capacity.map(|capacity| BoundedCrane::new(capacity.get()));
+    let delay = advent_of_code::unwrap_or_exit(args.opt_value_from_str("--delay")).unwrap_or(250);
+    let undo: Option<String> = advent_of_code::unwrap_or_exit(args.opt_value_from_str("--undo"));
+    let verify_end: Option<String> =
+        advent_of_code::unwrap_or_exit(args.opt_value_from_str("--verify"));
+
+    let crane: &dyn Crane = match (&bounded, part) {
+        (Some(bounded), _) => bounded,
+        (None, 1) => &CrateMover9000,
+        (None, _) => &CrateMover9001,
+    };
+
+    if let Some(path) = undo {
+        let (end, moves) = advent_of_code::unwrap_or_exit(parse_backwards(&read_drawing(&path)));
+        let start = advent_of_code::unwrap_or_exit(unwind(&end, &moves, crane));
+        print!("{}", render_input(&start, &moves));
+        return;
+    }
+
     let input = &advent_of_code::read_file("inputs", 5);
-    advent_of_code::solve!(1, part_one, input);
-    advent_of_code::solve!(2, part_two, input);
+    let procedure = advent_of_code::unwrap_or_exit(advent_of_code::parse!(parse_input, input));
+
+    if let Some(path) = verify_end {
+        let end = advent_of_code::unwrap_or_exit(parse_stacks(&read_drawing(&path)));
+        let (start, moves) = &procedure;
+        if verify(start, moves, &end, crane) {
+            println!("Moves result in the given end state.");
+        } else {
+            println!("Moves do not result in the given end state.");
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    if trace || animate {
+        let delay = animate.then(|| Duration::from_millis(delay));
+        show_simulation(&procedure, crane, delay);
+        return;
+    }
+
+    advent_of_code::solve!(1, part_one, &procedure);
+    advent_of_code::solve!(2, part_two, &procedure);
+
+    let (stacks, moves) = &procedure;
+    println!("Lifts:");
+    println!(
+        "CrateMover 9000: {}",
+        operate(stacks, moves, &CrateMover9000).1.lifts
+    );
+    println!(
+        "CrateMover 9001: {}",
+        operate(stacks, moves, &CrateMover9001).1.lifts
+    );
+    if let Some(bounded) = &bounded {
+        let (stacks, lifts) = operate(stacks, moves, bounded);
+        println!(
+            "Capacity {}: {} ({})",
+            bounded.capacity,
+            lifts.lifts,
+            stacks.message()
+        );
+    }
 }
 
 #[cfg(test)]
@@ -111,12 +740,282 @@ mod tests {
     #[test]
     fn test_part_one() {
         let input = advent_of_code::read_file("examples", 5);
-        assert_eq!(part_one(&input).unwrap(), "CMZ");
+        assert_eq!(part_one(&parse_input(&input).unwrap()).unwrap(), "CMZ");
     }
 
     #[test]
     fn test_part_two() {
         let input = advent_of_code::read_file("examples", 5);
-        assert_eq!(part_two(&input).unwrap(), "MCD");
+        assert_eq!(part_two(&parse_input(&input).unwrap()).unwrap(), "MCD");
+    }
+
+    #[test]
+    fn test_render() {
+        let input = advent_of_code::read_file("examples", 5);
+        let (stacks, moves) = parse_input(&input).unwrap();
+        let (drawing, _) = input.split_once("\n\n").unwrap();
+        assert_eq!(stacks.to_string(), drawing.to_string() + "\n");
+        assert_eq!(moves[1].to_string(), "move 3 from 1 to 3");
+    }
+
+    #[test]
+    fn test_trace() {
+        let input = advent_of_code::read_file("examples", 5);
+        let (stacks, moves) = parse_input(&input).unwrap();
+
+        let states = trace(&stacks, &moves, &CrateMover9000);
+        assert_eq!(states.len(), moves.len() + 1);
+        assert_eq!(states[0].to_string(), stacks.to_string());
+        assert_eq!(
+            states[1].to_string(),
+            "[D]        \n[N] [C]    \n[Z] [M] [P]\n 1   2   3 \n"
+        );
+        assert_eq!(states.last().unwrap().message(), "CMZ");
+        assert_eq!(
+            trace(&stacks, &moves, &CrateMover9001)
+                .last()
+                .unwrap()
+                .message(),
+            "MCD"
+        );
+    }
+
+    #[test]
+    fn test_cranes() {
+        let input = advent_of_code::read_file("examples", 5);
+        let (stacks, moves) = parse_input(&input).unwrap();
+        let run = |crane: &dyn Crane| {
+            let states = trace(&stacks, &moves, crane);
+            states.last().unwrap().message()
+        };
+
+        assert_eq!(run(&BoundedCrane::new(1)), "CMZ");
+        assert_eq!(run(&BoundedCrane::new(3)), "MCD");
+        assert_eq!(run(&BoundedCrane::new(2)), "MCZ");
+
+        let lifts = |crane: &dyn Crane| operate(&stacks, &moves, crane).1;
+        assert_eq!(
+            lifts(&CrateMover9000),
+            Lifts {
+                lifts: 7,
+                crates: 7
+            }
+        );
+        assert_eq!(
+            lifts(&CrateMover9001),
+            Lifts {
+                lifts: 4,
+                crates: 7
+            }
+        );
+        assert_eq!(lifts(&BoundedCrane::new(2)).lifts, 5);
+        assert_eq!(lifts(&CrateMover9001).cost(10, 1), 47);
+    }
+
+    #[test]
+    fn test_undo() {
+        let input = advent_of_code::read_file("examples", 5);
+        let (start, moves) = parse_input(&input).unwrap();
+
+        let cranes: [&dyn Crane; 3] = [&CrateMover9000, &CrateMover9001, &BoundedCrane::new(2)];
+        for crane in cranes {
+            let (end, _) = operate(&start, &moves, crane);
+            assert_eq!(unwind(&end, &moves, crane), Ok(start.clone()));
+            assert!(verify(&start, &moves, &end, crane));
+        }
+
+        let (end, _) = operate(&start, &moves, &CrateMover9000);
+        assert!(!verify(&start, &moves, &end, &CrateMover9001));
+        assert!(!verify(&end, &moves, &start, &CrateMover9000));
+    }
+
+    #[test]
+    fn test_construct_backwards() {
+        let input = "        [C]\n        [D]\n[A] [B] [E]\n 1   2   3\n\nmove 1 from 1 to 2\nmove 2 from 2 to 3\n";
+        let (end, moves) = parse_backwards(input).unwrap();
+        let start = unwind(&end, &moves, &CrateMover9000).unwrap();
+
+        let generated = render_input(&start, &moves);
+        assert_eq!(
+            generated,
+            "[D] [C]    \n[A] [B] [E]\n 1   2   3 \n\nmove 1 from 1 to 2\nmove 2 from 2 to 3\n"
+        );
+        assert_eq!(part_one(&parse(&generated).unwrap()).unwrap(), "ABC");
+
+        let end = parse_stacks("[A] [B] [C]\n 1   2   3\n").unwrap();
+        assert_eq!(
+            unwind(&end, &moves, &CrateMover9000)
+                .unwrap_err()
+                .to_string(),
+            "cannot undo move 2: stack 3 only has 1 crates"
+        );
+    }
+
+    #[test]
+    fn test_flexible_drawings() {
+        let input = "    [d]\n[n] [c]\n[z] [m] [p]\n 1   2   3\n\nmove 1 from 2 to 1\n";
+        let procedure = parse(input).unwrap();
+        assert_eq!(part_one(&procedure).unwrap(), "dcp");
+
+        let input = "\
+[A]                                         [L]
+[B] [C] [D] [E] [F] [G] [H] [I] [J] [K] [M] [N]
+ 1   2   3   4   5   6   7   8   9  10  11  12
+
+move 2 from 1 to 12
+move 1 from 12 to 10
+";
+        let procedure = parse(input).unwrap();
+        assert_eq!(part_one(&procedure).unwrap(), "CDEFGHIJBMA");
+        assert_eq!(part_two(&procedure).unwrap(), "CDEFGHIJAMB");
+        assert_eq!(
+            parse(&procedure.0.to_string()).unwrap().0.to_string(),
+            procedure.0.to_string()
+        );
+    }
+
+    #[test]
+    fn test_invalid_drawings() {
+        let error = |input| parse(input).unwrap_err().to_string();
+        assert_eq!(error(""), "line 1: expected a row of stack numbers");
+        assert_eq!(
+            error("[A]\n\n"),
+            "line 1: expected stack number 1, found \"[A]\""
+        );
+        assert_eq!(
+            error("[A]\n 1   3\n"),
+            "line 2: expected stack number 2, found \"3\""
+        );
+        assert_eq!(
+            error("[A] [B\n 1   2\n"),
+            "line 1: column 7: unexpected end of line"
+        );
+        assert_eq!(error("[A] x\n 1   2\n"), "line 1: column 5: unexpected 'x'");
+        assert_eq!(
+            error("[A] [B]\n 1\n"),
+            "line 1: column 6: crate is not above a stack number"
+        );
+        assert_eq!(
+            error("[A] [B]\n[C]\n 1   2\n"),
+            "line 1: crate on stack 2 is not resting on another crate"
+        );
+    }
+
+    #[test]
+    fn test_invalid_moves() {
+        let error = |moves| {
+            parse(&format!("[A]\n[B] [C]\n 1   2\n\n{moves}"))
+                .unwrap_err()
+                .to_string()
+        };
+        assert_eq!(
+            error("move 1 from 1 to 2\nmove 1 from 1\n"),
+            "line 6: expected \"move <n> from <stack> to <stack>\""
+        );
+        assert_eq!(error("move 1 from 3 to 2\n"), "line 5: there is no stack 3");
+        assert_eq!(error("move 1 from 1 to 0\n"), "line 5: there is no stack 0");
+        assert_eq!(
+            error("move 1 from 2 to 2\n"),
+            "line 5: move from stack 2 onto itself"
+        );
+        assert_eq!(
+            error("move 1 from 2 to 1\nmove 4 from 1 to 2\n"),
+            "line 6: stack 1 only has 3 crates"
+        );
+    }
+
+    #[test]
+    fn test_matches_reference() {
+        for seed in 0..200 {
+            let input = advent_of_code::generators::day05::generate(seed, seed as usize);
+            let procedure = parse_input(&input).unwrap();
+            assert_eq!(
+                part_one(&procedure),
+                reference::part_one(&input),
+                "seed {seed}"
+            );
+            assert_eq!(
+                part_two(&procedure),
+                reference::part_two(&input),
+                "seed {seed}"
+            );
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "unchecked")]
+    fn test_unchecked_matches_checked() {
+        for seed in 0..200 {
+            let input = advent_of_code::generators::day05::generate(seed, seed as usize);
+            let (stacks, moves) = parse(&input).unwrap();
+            let (fast_stacks, fast_moves) = unchecked::parse_input(&input);
+            assert_eq!(fast_stacks, stacks, "seed {seed}");
+            assert_eq!(
+                render_input(&fast_stacks, &fast_moves),
+                render_input(&stacks, &moves),
+                "seed {seed}"
+            );
+        }
+    }
+}
+
+/// Slow but obviously correct solutions to check the solutions against, with either parser.
+#[cfg(test)]
+mod reference {
+    fn parse(input: &str) -> (Vec<Vec<char>>, Vec<[usize; 3]>) {
+        let (drawing, moves) = input.split_once("\n\n").unwrap();
+        let mut rows: Vec<Vec<char>> = drawing.lines().map(|l| l.chars().collect()).collect();
+        let labels = rows.pop().unwrap();
+        let count = labels.iter().filter(|ch| ch.is_ascii_digit()).count();
+
+        let mut stacks = vec![vec![]; count];
+        for row in rows.iter().rev() {
+            for (i, stack) in stacks.iter_mut().enumerate() {
+                if let Some(&ch) = row.get(1 + 4 * i) {
+                    if ch != ' ' {
+                        stack.push(ch);
+                    }
+                }
+            }
+        }
+
+        let moves = moves
+            .lines()
+            .map(|line| {
+                let words: Vec<&str> = line.split(' ').collect();
+                [words[1], words[3], words[5]].map(|n| n.parse().unwrap())
+            })
+            .collect();
+
+        (stacks, moves)
+    }
+
+    fn top_crates(stacks: &[Vec<char>]) -> Option<String> {
+        Some(stacks.iter().filter_map(|stack| stack.last()).collect())
+    }
+
+    pub fn part_one(input: &str) -> Option<String> {
+        let (mut stacks, moves) = parse(input);
+        for [count, from, to] in moves {
+            for _ in 0..count {
+                let krate = stacks[from - 1].pop().unwrap();
+                stacks[to - 1].push(krate);
+            }
+        }
+        top_crates(&stacks)
+    }
+
+    pub fn part_two(input: &str) -> Option<String> {
+        let (mut stacks, moves) = parse(input);
+        for [count, from, to] in moves {
+            let mut lifted = vec![];
+            for _ in 0..count {
+                lifted.push(stacks[from - 1].pop().unwrap());
+            }
+            while let Some(krate) = lifted.pop() {
+                stacks[to - 1].push(krate);
+            }
+        }
+        top_crates(&stacks)
     }
 }
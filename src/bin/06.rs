@@ -1,105 +1,149 @@
-#![feature(iter_next_chunk)]
-#![feature(array_windows)]
-use std::collections::VecDeque;
-
-#[derive(Debug)]
-struct Buf {
-    inner: VecDeque<u8>,
-    last_dup: Option<u8>,
-    char_count: [u8; 26],
+use advent_of_code::helpers::ByteSet;
+use std::{
+    fs::File,
+    io::{self, BufReader, Read},
+    num::NonZeroUsize,
+};
+
+/// Finds markers in a stream of bytes: positions after which the last `window` bytes were all
+/// different and in the alphabet. Only remembers where each byte value was last seen, so memory
+/// use doesn't depend on the window size or the length of the stream.
+#[derive(Clone, Debug)]
+pub struct MarkerDetector {
+    window: u64,
+    alphabet: ByteSet,
+    /// Number of bytes processed when each byte value was last seen, `0` if never.
+    last_seen: [u64; 256],
+    /// Number of bytes processed so far.
+    position: u64,
+    /// Start of the longest run of distinct bytes that ends at the current position.
+    run_start: u64,
 }
 
-impl Buf {
-    fn new(it: impl IntoIterator<Item = u8>) -> Self {
-        let mut char_count: [u8; 26] = Default::default();
-
-        let mut me = Self {
-            inner: VecDeque::from_iter(
-                it.into_iter()
-                    .inspect(|&ch| char_count[Self::char_to_idx(ch)] += 1),
-            ),
-            last_dup: None,
-            char_count,
-        };
-        me.last_dup = me.find_dup().copied();
-        me
+impl MarkerDetector {
+    /// Detector for markers of any bytes. Panics if `window` is zero.
+    pub fn new(window: usize) -> Self {
+        Self::with_alphabet(window, ByteSet::ALL)
     }
 
-    fn char_to_idx(ch: u8) -> usize {
-        (ch - b'a') as usize
+    /// Detector for markers made of bytes in `alphabet` only. Other bytes still count towards
+    /// positions, but no marker contains them. Panics if `window` is zero.
+    pub fn with_alphabet(window: usize, alphabet: ByteSet) -> Self {
+        assert!(window > 0, "Marker window must not be empty");
+        Self {
+            window: window as u64,
+            alphabet,
+            last_seen: [0; 256],
+            position: 0,
+            run_start: 0,
+        }
     }
 
-    fn get_count(&self, ch: u8) -> u8 {
-        self.char_count[Self::char_to_idx(ch)]
-    }
+    /// Processes the next byte, returns the number of bytes processed so far if they end with
+    /// a marker.
+    pub fn push(&mut self, byte: u8) -> Option<u64> {
+        self.position += 1;
+        if !self.alphabet.contains(byte) {
+            self.run_start = self.position;
+            return None;
+        }
 
-    fn dec_count(&mut self, ch: u8) {
-        self.char_count[Self::char_to_idx(ch)] -= 1;
-    }
+        let last_seen = &mut self.last_seen[byte as usize];
+        self.run_start = self.run_start.max(*last_seen);
+        *last_seen = self.position;
 
-    fn inc_count(&mut self, ch: u8) {
-        self.char_count[Self::char_to_idx(ch)] += 1;
+        (self.position - self.run_start >= self.window).then_some(self.position)
     }
+}
 
-    fn advance(&mut self, item: u8) -> Option<u8> {
-        let evicted = self.inner.pop_front();
-        self.inner.push_back(item);
-
-        self.inc_count(item);
-        let e = unsafe { evicted.unwrap_unchecked() };
-        self.dec_count(e);
-
-        if evicted == self.last_dup && self.get_count(e) < 2 {
-            self.last_dup = self.find_dup().copied()
-        }
-
-        evicted
-    }
+/// Bytes that can be part of a marker in the puzzle input: anything but line breaks.
+pub fn datastream_alphabet() -> ByteSet {
+    let mut alphabet = ByteSet::ALL;
+    alphabet.remove(b'\n');
+    alphabet.remove(b'\r');
+    alphabet
+}
 
-    fn find_dup(&self) -> Option<&u8> {
-        self.inner.iter().rev().find(|ch| self.get_count(**ch) > 1)
-    }
+/// Positions of all markers in `bytes`, as the number of bytes processed up to each of them.
+pub fn markers(
+    bytes: impl IntoIterator<Item = u8>,
+    mut detector: MarkerDetector,
+) -> impl Iterator<Item = u64> {
+    bytes
+        .into_iter()
+        .filter_map(move |byte| detector.push(byte))
 }
 
-pub fn part_one(input: &str) -> Option<u32> {
-    const N: usize = 4;
-    input
-        .as_bytes()
-        .array_windows::<N>()
-        .zip(N..)
-        .find_map(|(a, count)| {
-            for i in 1..N {
-                for j in 0..i {
-                    if a[i] == a[j] {
-                        return None;
-                    }
-                }
-            }
-            Some(count as u32)
+/// Like [`markers`], but reads the bytes from `reader` as they are needed.
+pub fn markers_in_reader(
+    reader: impl Read,
+    mut detector: MarkerDetector,
+) -> impl Iterator<Item = io::Result<u64>> {
+    BufReader::new(reader)
+        .bytes()
+        .filter_map(move |byte| match byte {
+            Ok(byte) => detector.push(byte).map(Ok),
+            Err(err) => Some(Err(err)),
         })
 }
 
-pub fn part_two(input: &str) -> Option<u32> {
-    let mut bytes = input.bytes();
+pub fn part_one(input: &str) -> Option<u64> {
+    markers(
+        input.bytes(),
+        MarkerDetector::with_alphabet(4, datastream_alphabet()),
+    )
+    .next()
+}
 
-    let mut buf = Buf::new(
-        bytes
-            .by_ref()
-            .next_chunk::<14>()
-            .expect("Bad input: Not enough bytes to start"),
-    );
+pub fn part_two(input: &str) -> Option<u64> {
+    markers(
+        input.bytes(),
+        MarkerDetector::with_alphabet(14, datastream_alphabet()),
+    )
+    .next()
+}
 
-    for (b, count) in bytes.zip(15..) {
-        buf.advance(b);
+fn main() {
+    let mut args = pico_args::Arguments::from_env();
+    let window: Option<NonZeroUsize> =
+        advent_of_code::unwrap_or_exit(args.opt_value_from_str("--window"));
+    let stream: Option<String> =
+        advent_of_code::unwrap_or_exit(args.opt_value_from_str("--stream"));
+    let alphabet: Option<String> =
+        advent_of_code::unwrap_or_exit(args.opt_value_from_str("--alphabet"));
+    let all = args.contains("--all");
+
+    if let Some(window) = window.map(NonZeroUsize::get) {
+        let alphabet = alphabet.map_or_else(datastream_alphabet, |alphabet| {
+            ByteSet::from_bytes(alphabet.as_bytes())
+        });
+        let detector = MarkerDetector::with_alphabet(window, alphabet);
+        let found: Box<dyn Iterator<Item = io::Result<u64>>> = match &stream {
+            Some(path) => Box::new(markers_in_reader(
+                advent_of_code::unwrap_or_exit(File::open(path)),
+                detector,
+            )),
+            None => Box::new(
+                markers(
+                    advent_of_code::read_file("inputs", 6).into_bytes(),
+                    detector,
+                )
+                .map(Ok),
+            ),
+        };
 
-        if buf.last_dup.is_none() {
-            return Some(count);
+        let mut count = 0;
+        for position in found {
+            let position = advent_of_code::unwrap_or_exit(position);
+            if all || count == 0 {
+                println!("Marker after {position} bytes");
+            }
+            count += 1;
         }
+        println!("Markers of {window} distinct bytes: {count}");
+        return;
     }
-    None
-}
 
-fn main() {
     let input = &advent_of_code::read_file("inputs", 6);
     advent_of_code::solve!(1, part_one, input);
     advent_of_code::solve!(2, part_two, input);
@@ -118,6 +162,69 @@ mod tests {
     #[test]
     fn test_part_two() {
         let input = advent_of_code::read_file("examples", 6);
-        assert_eq!(part_two(&input), None);
+        assert_eq!(part_two(&input), Some(19));
+    }
+
+    #[test]
+    fn test_other_examples() {
+        assert_eq!(part_one("bvwbjplbgvbhsrlpgdmjqwftvncz"), Some(5));
+        assert_eq!(part_one("nznrnfrfntjfmvfwmzdfjlvtqnbhcprsg"), Some(10));
+        assert_eq!(part_two("zcfzfwzzqfrljwzlrfnpqdbhtmscgvjw"), Some(26));
+        assert_eq!(part_two("abc"), None);
+    }
+
+    #[test]
+    fn test_all_markers() {
+        assert_eq!(
+            markers("abcabba".bytes(), MarkerDetector::new(3)).collect::<Vec<_>>(),
+            [3, 4, 5]
+        );
+        assert_eq!(
+            markers("aab".bytes(), MarkerDetector::new(1)).collect::<Vec<_>>(),
+            [1, 2, 3]
+        );
+
+        let from_reader: Vec<_> = markers_in_reader("abcabba".as_bytes(), MarkerDetector::new(3))
+            .map(Result::unwrap)
+            .collect();
+        assert_eq!(from_reader, [3, 4, 5]);
+    }
+
+    #[test]
+    fn test_any_bytes() {
+        assert_eq!(part_one("abc\n"), None);
+        assert_eq!(part_one("aB1\nXyZ!"), Some(8));
+        assert_eq!(part_one("ab\r\ncd"), None);
+
+        let bytes = [0xff, 0x00, 0xff, 0x80, 0x7f];
+        let found: Vec<_> = markers(bytes, MarkerDetector::new(3)).collect();
+        assert_eq!(found, [4, 5]);
+
+        let vowels = ByteSet::from_bytes(b"aeiou");
+        let found: Vec<_> =
+            markers("aeixoua".bytes(), MarkerDetector::with_alphabet(2, vowels)).collect();
+        assert_eq!(found, [2, 3, 6, 7]);
+    }
+
+    #[test]
+    fn test_matches_windows() {
+        let mut rng = advent_of_code::generators::Rng::new(6);
+        for seed in 0..50 {
+            let input = advent_of_code::generators::day06::generate(seed, 200);
+            let window = 1 + rng.below(20);
+
+            let expected: Vec<u64> = input
+                .as_bytes()
+                .windows(window)
+                .zip(window as u64..)
+                .filter(|(bytes, _)| (1..bytes.len()).all(|i| !bytes[..i].contains(&bytes[i])))
+                .map(|(_, position)| position)
+                .collect();
+            assert_eq!(
+                markers(input.bytes(), MarkerDetector::new(window)).collect::<Vec<_>>(),
+                expected,
+                "seed {seed}, window {window}"
+            );
+        }
     }
 }
@@ -1,235 +1,541 @@
-#![feature(once_cell)]
 use std::{
-    cell::{OnceCell, RefCell},
-    fmt::Debug,
-    rc::Rc,
+    fmt::{Display, Write},
+    fs::{self, File},
+    io,
+    path::{Path, PathBuf},
 };
 
-type WrappedNode = Rc<RefCell<FsEntry>>;
+/// Index of a node in [`FileTree::nodes`].
+type NodeId = usize;
 
-struct Children {
-    inner: Vec<WrappedNode>,
+/// Entry of the filesystem. Children of a directory are linked through `next_sibling`, in the
+/// order they were listed.
+#[derive(Debug)]
+struct Node<'a> {
+    name: &'a str,
+    parent: Option<NodeId>,
+    first_child: Option<NodeId>,
+    last_child: Option<NodeId>,
+    next_sibling: Option<NodeId>,
+    /// Size of the file, or total size of everything in the directory.
+    size: u64,
+    is_dir: bool,
 }
 
-impl Debug for Children {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        f.debug_list().entries(&self.inner).finish()
+impl<'a> Node<'a> {
+    fn new(name: &'a str, parent: Option<NodeId>, size: u64, is_dir: bool) -> Self {
+        Self {
+            name,
+            parent,
+            first_child: None,
+            last_child: None,
+            next_sibling: None,
+            size,
+            is_dir,
+        }
     }
 }
 
-impl Children {
-    fn new() -> Self {
-        Self { inner: Vec::new() }
+pub(crate) enum Line<'a> {
+    Cd(&'a str),
+    Ls,
+    Dir(&'a str),
+    File(&'a str, u64),
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum ErrorKind {
+    EmptyLine,
+    UnknownCommand(String),
+    MissingArgument(&'static str),
+    UnexpectedArgument(String),
+    InvalidEntry(String),
+    InvalidName(String),
+    OutputWithoutLs,
+    NoSuchDirectory(String),
+    NotADirectory(String),
+    ConflictingEntry(String),
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct ParseError {
+    line: usize,
+    kind: ErrorKind,
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "line {}: ", self.line)?;
+        match &self.kind {
+            ErrorKind::EmptyLine => write!(f, "expected a command or output of ls"),
+            ErrorKind::UnknownCommand(cmd) => write!(f, "unknown command {cmd:?}"),
+            ErrorKind::MissingArgument(what) => write!(f, "missing {what}"),
+            ErrorKind::UnexpectedArgument(arg) => write!(f, "unexpected argument {arg:?}"),
+            ErrorKind::InvalidEntry(entry) => {
+                write!(f, "expected \"dir\" or a file size, found {entry:?}")
+            }
+            ErrorKind::InvalidName(name) => write!(f, "{name:?} is not a valid name"),
+            ErrorKind::OutputWithoutLs => write!(f, "output that does not belong to ls"),
+            ErrorKind::NoSuchDirectory(name) => write!(f, "no such directory {name:?}"),
+            ErrorKind::NotADirectory(name) => write!(f, "{name:?} is not a directory"),
+            ErrorKind::ConflictingEntry(name) => {
+                write!(
+                    f,
+                    "{name:?} was listed before with a different type or size"
+                )
+            }
+        }
     }
+}
+
+impl std::error::Error for ParseError {}
 
-    fn add_child(&mut self, node: FsEntry) -> WrappedNode {
-        let child = node.wrap();
-        self.inner.push(Rc::clone(&child));
-        child
+fn name<'a>(name: Option<&'a str>, what: &'static str) -> Result<&'a str, ErrorKind> {
+    match name {
+        None => Err(ErrorKind::MissingArgument(what)),
+        Some(name) if name.contains('/') || name == "." || name == ".." => {
+            Err(ErrorKind::InvalidName(name.to_string()))
+        }
+        Some(name) => Ok(name),
     }
+}
 
-    fn get_child(&self, name: impl AsRef<str>) -> Option<WrappedNode> {
-        self.inner
-            .iter()
-            .find(|child| name.as_ref().eq(child.borrow_mut().name()))
-            .map(Rc::clone)
+pub(crate) fn parse_line(line: &str) -> Result<Line<'_>, ErrorKind> {
+    let mut splits = line.split_ascii_whitespace();
+
+    let parsed = match splits.next() {
+        None => return Err(ErrorKind::EmptyLine),
+        Some("$") => match splits.next() {
+            Some("cd") => match splits.next() {
+                Some(target @ ("/" | "..")) => Line::Cd(target),
+                target => Line::Cd(name(target, "target of cd")?),
+            },
+            Some("ls") => Line::Ls,
+            Some(cmd) => return Err(ErrorKind::UnknownCommand(cmd.to_string())),
+            None => return Err(ErrorKind::MissingArgument("command")),
+        },
+        Some("dir") => Line::Dir(name(splits.next(), "directory name")?),
+        Some(size) => {
+            let size = size
+                .parse()
+                .map_err(|_| ErrorKind::InvalidEntry(size.to_string()))?;
+            Line::File(name(splits.next(), "file name")?, size)
+        }
+    };
+
+    match splits.next() {
+        Some(arg) => Err(ErrorKind::UnexpectedArgument(arg.to_string())),
+        None => Ok(parsed),
     }
 }
 
+/// How to deal with transcripts that don't follow the rules of the puzzle.
+#[derive(Clone, Debug, Default)]
+pub struct TranscriptOptions {
+    /// Create directories that are changed into before they are listed, instead of failing.
+    pub create_missing_dirs: bool,
+}
+
+/// All entries of the filesystem in one arena, with names borrowed from the transcript.
+/// Every node comes after its parent, the root is the first node.
 #[derive(Debug)]
-enum FsEntry {
-    File {
-        name: String,
-        size: usize,
-    },
-    Folder {
-        name: String,
-        children: Children,
-        size: OnceCell<usize>,
-    },
+pub struct FileTree<'a> {
+    nodes: Vec<Node<'a>>,
 }
 
-impl FsEntry {
-    pub fn new_file(name: String, size: usize) -> Self {
-        Self::File { name, size }
-    }
+const ROOT: NodeId = 0;
 
-    pub fn new_folder(name: String) -> Self {
-        Self::Folder {
-            name,
-            children: Children::new(),
-            size: OnceCell::new(),
+impl<'a> FileTree<'a> {
+    fn new() -> Self {
+        Self {
+            nodes: vec![Node::new("/", None, 0, true)],
         }
     }
 
-    pub fn name(&self) -> &str {
-        match self {
-            FsEntry::File { name, .. } | FsEntry::Folder { name, .. } => name,
-        }
+    fn children(&self, id: NodeId) -> impl Iterator<Item = NodeId> + '_ {
+        std::iter::successors(self.nodes[id].first_child, |&child| {
+            self.nodes[child].next_sibling
+        })
+    }
+
+    fn child(&self, id: NodeId, name: &str) -> Option<NodeId> {
+        self.children(id)
+            .find(|&child| self.nodes[child].name == name)
     }
 
-    pub fn size(&self) -> usize {
-        match self {
-            FsEntry::File { size, .. } => *size,
-            FsEntry::Folder { children, size, .. } => *size.get_or_init(|| {
-                children
-                    .inner
-                    .iter()
-                    .map(|child| child.borrow().size())
-                    .sum()
-            }),
+    fn add_child(&mut self, parent: NodeId, name: &'a str, size: u64, is_dir: bool) -> NodeId {
+        let id = self.nodes.len();
+        self.nodes.push(Node::new(name, Some(parent), size, is_dir));
+
+        match self.nodes[parent].last_child.replace(id) {
+            Some(last) => self.nodes[last].next_sibling = Some(id),
+            None => self.nodes[parent].first_child = Some(id),
         }
+        id
     }
 
-    pub fn children(&self) -> Option<&Children> {
-        match self {
-            FsEntry::File { .. } => None,
-            FsEntry::Folder { children, .. } => Some(children),
+    /// Adds an entry listed by `ls`. Entries that were listed before are skipped, so listing a
+    /// directory twice doesn't count its files twice.
+    fn add_listed(
+        &mut self,
+        parent: NodeId,
+        name: &'a str,
+        size: u64,
+        is_dir: bool,
+    ) -> Result<(), ErrorKind> {
+        match self.child(parent, name) {
+            None => {
+                self.add_child(parent, name, size, is_dir);
+                Ok(())
+            }
+            Some(existing) => {
+                let existing = &self.nodes[existing];
+                if existing.is_dir == is_dir && (is_dir || existing.size == size) {
+                    Ok(())
+                } else {
+                    Err(ErrorKind::ConflictingEntry(name.to_string()))
+                }
+            }
         }
     }
 
-    pub fn children_mut(&mut self) -> Option<&mut Children> {
-        match self {
-            FsEntry::File { .. } => None,
-            FsEntry::Folder { children, .. } => Some(children),
+    /// Adds the size of every node to its parent. Children come after their parents, so
+    /// walking the arena backwards sees every directory's contents before the directory.
+    fn compute_sizes(&mut self) {
+        for id in (1..self.nodes.len()).rev() {
+            let Node { parent, size, .. } = self.nodes[id];
+            if let Some(parent) = parent {
+                // Saturates instead of overflowing on absurd transcripts, no real disk gets there.
+                self.nodes[parent].size = self.nodes[parent].size.saturating_add(size);
+            }
         }
     }
 
-    pub fn wrap(self) -> WrappedNode {
-        Rc::new(RefCell::new(self))
+    fn dirs(&self) -> impl Iterator<Item = (NodeId, &Node<'a>)> {
+        self.nodes
+            .iter()
+            .enumerate()
+            .filter(|(_, node)| node.is_dir)
     }
 
-    /// Returns `true` if the node is [`Folder`].
-    ///
-    /// [`Folder`]: Node::Folder
-    #[must_use]
-    fn is_folder(&self) -> bool {
-        matches!(self, Self::Folder { .. })
+    /// Absolute path of a node.
+    fn path(&self, id: NodeId) -> String {
+        let mut names = vec![];
+        let mut node = id;
+        while let Some(parent) = self.nodes[node].parent {
+            names.push(self.nodes[node].name);
+            node = parent;
+        }
+        names.reverse();
+        format!("/{}", names.join("/"))
     }
-}
 
-enum Line {
-    Cd(String),
-    Ls,
-    Node(FsEntry),
-}
+    /// Looks up the entry at an absolute path such as `/a/e`.
+    fn resolve(&self, path: &str) -> Option<NodeId> {
+        path.strip_prefix('/')?
+            .split('/')
+            .filter(|c| !c.is_empty())
+            .try_fold(ROOT, |node, name| self.child(node, name))
+    }
 
-fn parse_line(line: &str) -> Line {
-    let mut splits = line.split_ascii_whitespace();
+    /// Total size of the file or directory at `path`, like `du -s`.
+    pub fn du(&self, path: &str) -> Option<u64> {
+        self.resolve(path).map(|id| self.nodes[id].size)
+    }
 
-    let start = splits.next().expect("Line to be not empty");
+    /// Paths and total sizes of all directories, the root included, whose size matches
+    /// `predicate`.
+    pub fn find(&self, predicate: impl Fn(u64) -> bool) -> Vec<(String, u64)> {
+        self.dirs()
+            .filter(|(_, dir)| predicate(dir.size))
+            .map(|(id, dir)| (self.path(id), dir.size))
+            .collect()
+    }
 
-    if start == "$" {
-        let cmd = splits.next().expect("Command to be not empty");
-        match cmd {
-            "cd" => {
-                let dir = splits.next().expect("cd to have a target");
-                Line::Cd(dir.to_string())
+    /// Writes the entry at `id` and everything below it, one line per entry indented by
+    /// its depth. Walks with an explicit stack because transcripts can nest very deeply.
+    fn write_entry(&self, f: &mut std::fmt::Formatter<'_>, id: NodeId) -> std::fmt::Result {
+        let mut pending = vec![(id, 0)];
+        while let Some((id, depth)) = pending.pop() {
+            let node = &self.nodes[id];
+            for _ in 0..depth {
+                f.write_str("  ")?;
+            }
+            let kind = if node.is_dir { "dir" } else { "file" };
+            writeln!(f, "- {} ({kind}, size={})", node.name, node.size)?;
+
+            // Siblings of the starting entry are not below it.
+            if let Some(sibling) = node.next_sibling.filter(|_| depth > 0) {
+                pending.push((sibling, depth));
+            }
+            if let Some(child) = node.first_child {
+                pending.push((child, depth + 1));
             }
-            "ls" => Line::Ls,
-            _ => unreachable!("Unknown command"),
         }
-    } else if start == "dir" {
-        let dirname = splits.next().expect("Dirname to not be blank").to_string();
-        Line::Node(FsEntry::new_folder(dirname))
-    } else if let Ok(size) = start.parse() {
-        let filename = splits
-            .next()
-            .expect("File name to not be blank")
-            .to_string();
-        Line::Node(FsEntry::new_file(filename, size))
-    } else {
-        unreachable!()
+        Ok(())
     }
 }
 
-fn to_file_tree(input: &str) -> (WrappedNode, Vec<WrappedNode>) {
-    let lines = input.lines().map(parse_line);
+/// Prints the tree in the format of the puzzle description, with directory sizes added.
+impl Display for FileTree<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.write_entry(f, ROOT)
+    }
+}
 
-    let root = FsEntry::Folder {
-        name: "".into(),
-        children: Children::new(),
-        size: Default::default(),
+/// Writes `s` as a JSON string literal.
+fn write_json_string(out: &mut String, s: &str) {
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if c.is_control() => write!(out, "\\u{:04x}", c as u32).unwrap(),
+            c => out.push(c),
+        }
     }
-    .wrap();
-    let mut dirs = vec![];
-    let mut state = vec![Rc::downgrade(&root)];
+    out.push('"');
+}
 
-    for line in lines {
-        match line {
-            Line::Cd(target) => match target.as_str() {
-                "/" => {
-                    state.truncate(1);
-                }
-                ".." => {
-                    if state.len() > 1 {
-                        state.pop();
+impl FileTree<'_> {
+    /// The tree as JSON. Every entry is an object with `name`, `type` (`"dir"` or `"file"`)
+    /// and `size`, directories also list their `children` in the order they were listed.
+    pub fn to_json(&self) -> String {
+        let mut out = String::new();
+        self.open_json_entry(&mut out, ROOT);
+
+        // The remaining children of every directory that is still open, innermost last, so
+        // that deep trees don't need a stack frame per level.
+        let mut open = vec![self.children(ROOT)];
+        let mut first = true;
+        while let Some(children) = open.last_mut() {
+            match children.next() {
+                Some(child) => {
+                    if !first {
+                        out.push(',');
+                    }
+                    self.open_json_entry(&mut out, child);
+                    first = self.nodes[child].is_dir;
+                    if first {
+                        open.push(self.children(child));
+                    } else {
+                        out.push('}');
                     }
                 }
-                name => {
-                    let cwd = state.last().expect("State to not be empty");
-                    let child = cwd
-                        .upgrade()
-                        .expect("Node to be in memory")
-                        .borrow()
-                        .children()
-                        .expect("cwd to be a folder")
-                        .get_child(name)
-                        .expect("Child to exist");
-                    assert!(child.borrow().is_folder());
-                    state.push(Rc::downgrade(&child));
+                None => {
+                    open.pop();
+                    out.push_str("]}");
+                    first = false;
                 }
-            },
-            Line::Ls => {}
-            Line::Node(node) => {
-                let cwd = state.last().expect("State to not be empty");
-                let is_dir = node.is_folder();
-                let child = cwd
-                    .upgrade()
-                    .expect("Node to be in memory")
-                    .borrow_mut()
-                    .children_mut()
-                    .expect("cwd to be a folder")
-                    .add_child(node);
-                if is_dir {
-                    dirs.push(child);
+            }
+        }
+        out
+    }
+
+    /// Writes the fields of an entry, up to the opening bracket of `children` for directories.
+    fn open_json_entry(&self, out: &mut String, id: NodeId) {
+        let Node {
+            name, size, is_dir, ..
+        } = self.nodes[id];
+        out.push_str("{\"name\":");
+        write_json_string(out, name);
+        let kind = if is_dir { "dir" } else { "file" };
+        write!(out, ",\"type\":\"{kind}\",\"size\":{size}").unwrap();
+        if is_dir {
+            out.push_str(",\"children\":[");
+        }
+    }
+
+    /// Recreates the tree as real directories under `root`, which must not exist yet. Files
+    /// are created empty and extended to their size, which leaves them sparse on filesystems
+    /// that support it, so even large trees take next to no space. Compare with
+    /// `du --apparent-size` to cross-check the sizes.
+    pub fn materialize(&self, root: &Path) -> io::Result<()> {
+        fs::create_dir(root)?;
+        let mut pending: Vec<(NodeId, PathBuf)> = vec![(ROOT, root.to_path_buf())];
+        while let Some((id, path)) = pending.pop() {
+            for child in self.children(id) {
+                let node = &self.nodes[child];
+                let child_path = path.join(node.name);
+                if node.is_dir {
+                    fs::create_dir(&child_path)?;
+                    pending.push((child, child_path));
+                } else {
+                    File::create(&child_path)?.set_len(node.size)?;
                 }
             }
         }
+        Ok(())
     }
-    (root, dirs)
 }
 
-pub fn part_one(input: &str) -> Option<u32> {
-    let (_, subdirs) = to_file_tree(input);
-    Some(
-        subdirs
-            .iter()
-            .filter_map(|dir| {
-                let size = dir.borrow().size();
-                size.lt(&100000).then_some(size as u32)
-            })
-            .sum(),
-    )
+/// Replays a transcript of `cd` and `ls` commands with their output.
+pub fn parse_transcript<'a>(
+    input: &'a str,
+    options: &TranscriptOptions,
+) -> Result<FileTree<'a>, ParseError> {
+    let mut tree = FileTree::new();
+    let mut cwd = ROOT;
+    let mut listing = false;
+
+    for (line, line_no) in input.lines().zip(1..) {
+        let error = |kind| ParseError {
+            line: line_no,
+            kind,
+        };
+
+        match parse_line(line).map_err(error)? {
+            Line::Cd(target) => {
+                listing = false;
+                cwd = match target {
+                    "/" => ROOT,
+                    ".." => tree.nodes[cwd].parent.unwrap_or(ROOT),
+                    name => match tree.child(cwd, name) {
+                        Some(child) if tree.nodes[child].is_dir => child,
+                        Some(_) => return Err(error(ErrorKind::NotADirectory(name.to_string()))),
+                        None if options.create_missing_dirs => tree.add_child(cwd, name, 0, true),
+                        None => return Err(error(ErrorKind::NoSuchDirectory(name.to_string()))),
+                    },
+                };
+            }
+            Line::Ls => listing = true,
+            Line::Dir(_) | Line::File(..) if !listing => {
+                return Err(error(ErrorKind::OutputWithoutLs))
+            }
+            Line::Dir(name) => tree.add_listed(cwd, name, 0, true).map_err(error)?,
+            Line::File(name, size) => tree.add_listed(cwd, name, size, false).map_err(error)?,
+        }
+    }
+
+    tree.compute_sizes();
+    Ok(tree)
+}
+
+pub fn to_file_tree(input: &str) -> Result<FileTree<'_>, ParseError> {
+    parse_transcript(input, &TranscriptOptions::default())
+}
+
+/// Sizes the puzzle is about, in bytes.
+#[derive(Clone, Debug)]
+pub struct DiskConfig {
+    /// Total space on the disk.
+    pub capacity: u64,
+    /// Free space the update needs.
+    pub update_size: u64,
+    /// Directories of at most this size count as small.
+    pub small_dir_limit: u64,
+}
+
+impl Default for DiskConfig {
+    fn default() -> Self {
+        Self {
+            capacity: 70000000,
+            update_size: 30000000,
+            small_dir_limit: 100000,
+        }
+    }
+}
+
+/// Directory that frees up enough space for the update when deleted.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Deletion {
+    pub path: String,
+    pub size: u64,
 }
 
-pub fn part_two(input: &str) -> Option<u32> {
-    let (root, subdirs) = to_file_tree(input);
-    let to_free = 30000000 - (70000000 - root.borrow().size());
+impl Display for Deletion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} (size={})", self.path, self.size)
+    }
+}
 
-    subdirs
-        .iter()
-        .filter_map(|dir| {
-            let size = dir.borrow().size();
-            size.gt(&to_free).then_some(size as u32)
+/// Total size of all small directories below the root, counting nested ones once per
+/// directory they are in.
+pub fn small_dirs_total(tree: &FileTree, config: &DiskConfig) -> u64 {
+    tree.dirs()
+        .skip(1)
+        .map(|(_, dir)| dir.size)
+        .filter(|&size| size <= config.small_dir_limit)
+        .fold(0, u64::saturating_add)
+}
+
+/// Smallest directory below the root whose deletion frees up enough space for the update.
+/// `None` if there is enough space already or no directory is large enough.
+pub fn dir_to_delete(tree: &FileTree, config: &DiskConfig) -> Option<Deletion> {
+    let free = config.capacity.saturating_sub(tree.nodes[ROOT].size);
+    let to_free = config.update_size.checked_sub(free).filter(|&n| n > 0)?;
+
+    tree.dirs()
+        .skip(1)
+        .filter(|(_, dir)| dir.size >= to_free)
+        .min_by_key(|(_, dir)| dir.size)
+        .map(|(id, dir)| Deletion {
+            path: tree.path(id),
+            size: dir.size,
         })
-        .min()
+}
+
+pub fn part_one(tree: &FileTree) -> Option<u64> {
+    Some(small_dirs_total(tree, &DiskConfig::default()))
+}
+
+pub fn part_two(tree: &FileTree) -> Option<Deletion> {
+    dir_to_delete(tree, &DiskConfig::default())
 }
 
 fn main() {
+    let mut args = pico_args::Arguments::from_env();
+    let print_tree = args.contains("--tree");
+    let print_json = args.contains("--json");
+    let materialize: Option<PathBuf> =
+        advent_of_code::unwrap_or_exit(args.opt_value_from_str("--materialize"));
+    let options = TranscriptOptions {
+        create_missing_dirs: args.contains("--create-dirs"),
+    };
+    let du_paths: Vec<String> = advent_of_code::unwrap_or_exit(args.values_from_str("--du"));
+    let defaults = DiskConfig::default();
+    let config = DiskConfig {
+        capacity: advent_of_code::unwrap_or_exit(args.opt_value_from_str("--capacity"))
+            .unwrap_or(defaults.capacity),
+        update_size: advent_of_code::unwrap_or_exit(args.opt_value_from_str("--update-size"))
+            .unwrap_or(defaults.update_size),
+        small_dir_limit: advent_of_code::unwrap_or_exit(
+            args.opt_value_from_str("--small-dir-limit"),
+        )
+        .unwrap_or(defaults.small_dir_limit),
+    };
+
     let input = &advent_of_code::read_file("inputs", 7);
-    advent_of_code::solve!(1, part_one, input);
-    advent_of_code::solve!(2, part_two, input);
+    let tree = advent_of_code::unwrap_or_exit(advent_of_code::parse!(
+        |input| parse_transcript(input, &options),
+        input
+    ));
+    advent_of_code::solve!(
+        1,
+        |tree: &FileTree| Some(small_dirs_total(tree, &config)),
+        &tree
+    );
+    advent_of_code::solve!(2, |tree: &FileTree| dir_to_delete(tree, &config), &tree);
+
+    if print_tree {
+        print!("{tree}");
+    }
+    for path in du_paths {
+        match tree.du(&path) {
+            Some(size) => println!("{size}\t{path}"),
+            None => println!("{path}: no such file or directory"),
+        }
+    }
+    if print_json {
+        println!("{}", tree.to_json());
+    }
+    if let Some(root) = materialize {
+        advent_of_code::unwrap_or_exit(tree.materialize(&root));
+        println!("Materialized the tree under {}", root.display());
+    }
 }
 
 #[cfg(test)]
@@ -239,12 +545,429 @@ mod tests {
     #[test]
     fn test_part_one() {
         let input = advent_of_code::read_file("examples", 7);
-        assert_eq!(part_one(&input), Some(95437));
+        assert_eq!(part_one(&to_file_tree(&input).unwrap()), Some(95437));
     }
 
     #[test]
     fn test_part_two() {
         let input = advent_of_code::read_file("examples", 7);
-        assert_eq!(part_two(&input), Some(24933642));
+        assert_eq!(
+            part_two(&to_file_tree(&input).unwrap()),
+            Some(Deletion {
+                path: "/d".to_string(),
+                size: 24933642
+            })
+        );
+    }
+
+    #[test]
+    fn test_disk_config() {
+        let input = advent_of_code::read_file("examples", 7);
+        let tree = to_file_tree(&input).unwrap();
+
+        let config = DiskConfig {
+            capacity: 48500000,
+            update_size: 200000,
+            small_dir_limit: 1000,
+        };
+        assert_eq!(small_dirs_total(&tree, &config), 584);
+        assert_eq!(
+            dir_to_delete(&tree, &config).map(|d| d.to_string()),
+            Some("/a (size=94853)".to_string())
+        );
+
+        let exact = DiskConfig {
+            update_size: 21618835 + 94853,
+            small_dir_limit: 94853,
+            ..DiskConfig::default()
+        };
+        assert_eq!(small_dirs_total(&tree, &exact), 94853 + 584);
+        assert_eq!(
+            dir_to_delete(&tree, &exact).map(|d| d.to_string()),
+            Some("/a (size=94853)".to_string())
+        );
+
+        let roomy = DiskConfig {
+            capacity: 80000000,
+            ..DiskConfig::default()
+        };
+        assert_eq!(dir_to_delete(&tree, &roomy), None);
+
+        let huge = "$ cd /\n$ ls\ndir a\n$ cd a\n$ ls\n4000000000 b\n4000000000 c\n";
+        let tree = to_file_tree(huge).unwrap();
+        let config = DiskConfig {
+            capacity: 10000000000,
+            update_size: 9000000000,
+            small_dir_limit: 10000000000,
+        };
+        assert_eq!(small_dirs_total(&tree, &config), 8000000000);
+        assert_eq!(
+            dir_to_delete(&tree, &config),
+            Some(Deletion {
+                path: "/a".to_string(),
+                size: 8000000000
+            })
+        );
+    }
+
+    #[test]
+    fn test_queries() {
+        let input = advent_of_code::read_file("examples", 7);
+        let tree = to_file_tree(&input).unwrap();
+
+        assert_eq!(tree.du("/"), Some(48381165));
+        assert_eq!(tree.du("/a/e"), Some(584));
+        assert_eq!(tree.du("/a/e/"), Some(584));
+        assert_eq!(tree.du("/d/k"), Some(7214296));
+        assert_eq!(tree.du("/a/x"), None);
+        assert_eq!(tree.du("a"), None);
+
+        assert_eq!(
+            tree.find(|size| size <= 100000),
+            [("/a".to_string(), 94853), ("/a/e".to_string(), 584)]
+        );
+        assert_eq!(
+            tree.find(|size| size > 30000000),
+            [("/".to_string(), 48381165)]
+        );
+    }
+
+    #[test]
+    fn test_print_tree() {
+        let input = advent_of_code::read_file("examples", 7);
+        let printed = to_file_tree(&input).unwrap().to_string();
+        let lines: Vec<_> = printed.lines().collect();
+
+        assert_eq!(lines.len(), 14);
+        assert_eq!(lines[0], "- / (dir, size=48381165)");
+        assert_eq!(lines[1], "  - a (dir, size=94853)");
+        assert_eq!(lines[2], "    - e (dir, size=584)");
+        assert_eq!(lines[3], "      - i (file, size=584)");
+        assert_eq!(lines[13], "    - k (file, size=7214296)");
+    }
+
+    #[test]
+    fn test_json() {
+        let input = "$ cd /\n$ ls\ndir a\n14 b.txt\n$ cd a\n$ ls\n2 \"q\\\n";
+        assert_eq!(
+            to_file_tree(input).unwrap().to_json(),
+            concat!(
+                r#"{"name":"/","type":"dir","size":16,"children":["#,
+                r#"{"name":"a","type":"dir","size":2,"children":["#,
+                r#"{"name":"\"q\\","type":"file","size":2}]},"#,
+                r#"{"name":"b.txt","type":"file","size":14}]}"#
+            )
+        );
+    }
+
+    /// Sizes of all files below `path`, summed up per directory like `du --apparent-size`
+    /// without the directories themselves.
+    fn disk_usage(path: &Path, sizes: &mut Vec<(PathBuf, u64)>) -> u64 {
+        let metadata = fs::metadata(path).unwrap();
+        if metadata.is_file() {
+            return metadata.len();
+        }
+        let size = fs::read_dir(path)
+            .unwrap()
+            .map(|entry| disk_usage(&entry.unwrap().path(), sizes))
+            .sum();
+        sizes.push((path.to_path_buf(), size));
+        size
+    }
+
+    #[test]
+    fn test_materialize() {
+        let input = advent_of_code::read_file("examples", 7);
+        let tree = to_file_tree(&input).unwrap();
+        let root = std::env::temp_dir().join(format!("aoc-2022-day07-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+
+        tree.materialize(&root).unwrap();
+        let mut sizes = vec![];
+        disk_usage(&root, &mut sizes);
+        let result = tree.materialize(&root);
+        fs::remove_dir_all(&root).unwrap();
+
+        assert_eq!(sizes.len(), 4);
+        for (path, size) in sizes {
+            let relative = path.strip_prefix(&root).unwrap().to_str().unwrap();
+            assert_eq!(tree.du(&format!("/{relative}")), Some(size), "{relative}");
+        }
+        assert_eq!(result.unwrap_err().kind(), io::ErrorKind::AlreadyExists);
+    }
+
+    /// Transcript of directories nested `depth` levels deep, with a file at the bottom.
+    fn deep_transcript(depth: usize) -> String {
+        "$ ls\ndir a\n$ cd a\n".repeat(depth) + "$ ls\n1 b\n"
+    }
+
+    /// Runs `f` on a thread with a small stack, which a walk recursing once per level of a
+    /// deep tree would overflow.
+    fn with_small_stack<T: Send + 'static>(f: impl FnOnce() -> T + Send + 'static) -> T {
+        std::thread::Builder::new()
+            .stack_size(64 * 1024)
+            .spawn(f)
+            .unwrap()
+            .join()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_print_deep_tree() {
+        let printed = with_small_stack(|| {
+            let input = deep_transcript(5000);
+            to_file_tree(&input).unwrap().to_string()
+        });
+        let last = printed.lines().last().unwrap();
+        assert_eq!(printed.lines().count(), 5002);
+        assert_eq!(last.len() - last.trim_start().len(), 10002);
+        assert_eq!(last.trim_start(), "- b (file, size=1)");
+    }
+
+    #[test]
+    fn test_deep_json() {
+        let json = with_small_stack(|| {
+            let input = deep_transcript(5000);
+            to_file_tree(&input).unwrap().to_json()
+        });
+        let dir = r#"{"name":"a","type":"dir","size":1,"children":["#;
+        assert!(json.starts_with(r#"{"name":"/","type":"dir","size":1,"children":["#));
+        assert_eq!(json.matches(dir).count(), 5000);
+        assert!(json.ends_with(&format!(
+            r#"{dir}{{"name":"b","type":"file","size":1}}{}"#,
+            "]}".repeat(5001)
+        )));
+
+        let input = advent_of_code::generators::day07::generate(1, 2000);
+        let json = to_file_tree(&input).unwrap().to_json();
+        assert_eq!(json.matches('{').count(), json.matches('}').count());
+        assert_eq!(json.matches('[').count(), 2000);
+        assert_eq!(json.matches(']').count(), 2000);
+    }
+
+    #[test]
+    fn test_repeated_listings() {
+        let input = "$ cd /\n$ ls\ndir a\n1 b\n$ cd a\n$ ls\n2 c\n$ cd /\n$ ls\n1 b\ndir a\n$ cd a\n$ ls\n2 c\n";
+        let tree = to_file_tree(input).unwrap();
+        assert_eq!(tree.du("/"), Some(3));
+        assert_eq!(tree.to_string().lines().count(), 4);
+    }
+
+    #[test]
+    fn test_create_missing_dirs() {
+        let input = "$ cd /\n$ cd a\n$ ls\n2 c\n$ cd ..\n$ ls\n1 b\ndir a\n";
+        assert_eq!(
+            to_file_tree(input).unwrap_err().to_string(),
+            "line 2: no such directory \"a\""
+        );
+
+        let options = TranscriptOptions {
+            create_missing_dirs: true,
+        };
+        let tree = parse_transcript(input, &options).unwrap();
+        assert_eq!(tree.du("/"), Some(3));
+        assert_eq!(tree.du("/a"), Some(2));
+    }
+
+    #[test]
+    fn test_invalid_transcripts() {
+        let error = |input| to_file_tree(input).unwrap_err().to_string();
+        assert_eq!(
+            error("$ cd /\n$ ls\n$ rm -rf a\n"),
+            "line 3: unknown command \"rm\""
+        );
+        assert_eq!(
+            error("$ cd /\n\n"),
+            "line 2: expected a command or output of ls"
+        );
+        assert_eq!(error("$ cd\n"), "line 1: missing target of cd");
+        assert_eq!(error("$ ls -l\n"), "line 1: unexpected argument \"-l\"");
+        assert_eq!(
+            error("$ ls\nfile a\n"),
+            "line 2: expected \"dir\" or a file size, found \"file\""
+        );
+        assert_eq!(error("$ ls\n12\n"), "line 2: missing file name");
+        assert_eq!(
+            error("$ ls\ndir a/b\n"),
+            "line 2: \"a/b\" is not a valid name"
+        );
+        assert_eq!(
+            error("$ cd /\n1 a\n"),
+            "line 2: output that does not belong to ls"
+        );
+        assert_eq!(
+            error("$ ls\n1 a\n$ cd a\n"),
+            "line 3: \"a\" is not a directory"
+        );
+        assert_eq!(
+            error("$ ls\n1 a\n$ ls\n2 a\n"),
+            "line 4: \"a\" was listed before with a different type or size"
+        );
+    }
+
+    #[test]
+    fn test_matches_rc_tree() {
+        for seed in 0..50 {
+            let input = advent_of_code::generators::day07::generate(seed, 1 + seed as usize * 10);
+            let tree = to_file_tree(&input).unwrap();
+            let rc_tree = rc_tree::to_file_tree(&input);
+            assert_eq!(part_one(&tree), rc_tree::part_one(&rc_tree), "seed {seed}");
+            assert_eq!(
+                part_two(&tree).map(|deletion| deletion.size),
+                rc_tree::part_two(&rc_tree),
+                "seed {seed}"
+            );
+        }
+    }
+
+    /// Compares the arena with the tree of reference-counted nodes it replaced, run with
+    /// `cargo test --release --bin 07 -- --ignored --nocapture`. Add
+    /// `--features count-allocations` to compare allocations as well.
+    #[test]
+    #[ignore]
+    fn bench_arena_vs_rc_tree() {
+        use std::time::Instant;
+
+        fn measure<T>(label: &str, run: impl Fn() -> T) -> T {
+            advent_of_code::allocator::reset();
+            let timer = Instant::now();
+            let result = run();
+            let elapsed = timer.elapsed();
+            match advent_of_code::allocator::stats() {
+                Some(stats) => println!("{label}: {elapsed:.2?} ({stats})"),
+                None => println!("{label}: {elapsed:.2?}"),
+            }
+            result
+        }
+
+        for size in [1_000, 10_000, 100_000] {
+            let input = advent_of_code::generators::day07::generate(7, size);
+            println!("{size} directories, {} lines", input.lines().count());
+
+            let rc_tree = measure("  rc build   ", || rc_tree::to_file_tree(&input));
+            let tree = measure("  arena build", || to_file_tree(&input).unwrap());
+            let answers = measure("  arena parts", || {
+                (
+                    part_one(&tree),
+                    part_two(&tree).map(|deletion| deletion.size),
+                )
+            });
+            let rc_answers = measure("  rc parts   ", || {
+                (rc_tree::part_one(&rc_tree), rc_tree::part_two(&rc_tree))
+            });
+            assert_eq!(answers, rc_answers);
+        }
+    }
+}
+
+/// The previous tree of `Rc<RefCell>` nodes, kept as a baseline for the arena.
+#[cfg(test)]
+mod rc_tree {
+    use super::{parse_line, Line};
+    use std::{
+        cell::{OnceCell, RefCell},
+        rc::{Rc, Weak},
+    };
+
+    type WrappedNode = Rc<RefCell<FsEntry>>;
+
+    enum FsEntry {
+        File(String, u64),
+        Folder(String, Vec<WrappedNode>, OnceCell<u64>),
+    }
+
+    impl FsEntry {
+        fn name(&self) -> &str {
+            match self {
+                FsEntry::File(name, _) | FsEntry::Folder(name, ..) => name,
+            }
+        }
+
+        fn size(&self) -> u64 {
+            match self {
+                FsEntry::File(_, size) => *size,
+                FsEntry::Folder(_, children, size) => {
+                    *size.get_or_init(|| children.iter().map(|child| child.borrow().size()).sum())
+                }
+            }
+        }
+
+        fn children_mut(&mut self) -> &mut Vec<WrappedNode> {
+            match self {
+                FsEntry::File(..) => panic!("cwd to be a folder"),
+                FsEntry::Folder(_, children, _) => children,
+            }
+        }
+    }
+
+    pub struct FileTree {
+        root: WrappedNode,
+        dirs: Vec<WrappedNode>,
+    }
+
+    pub fn to_file_tree(input: &str) -> FileTree {
+        let root = Rc::new(RefCell::new(FsEntry::Folder(
+            "/".into(),
+            vec![],
+            OnceCell::new(),
+        )));
+        let mut dirs = vec![];
+        let mut state: Vec<Weak<RefCell<FsEntry>>> = vec![Rc::downgrade(&root)];
+
+        for line in input.lines().map(|line| parse_line(line).unwrap()) {
+            let cwd = state.last().unwrap().upgrade().unwrap();
+            let entry = match line {
+                Line::Cd("/") => {
+                    state.truncate(1);
+                    continue;
+                }
+                Line::Cd("..") => {
+                    if state.len() > 1 {
+                        state.pop();
+                    }
+                    continue;
+                }
+                Line::Cd(name) => {
+                    let child = cwd
+                        .borrow_mut()
+                        .children_mut()
+                        .iter()
+                        .find(|child| child.borrow_mut().name() == name)
+                        .map(Rc::clone)
+                        .expect("Child to exist");
+                    state.push(Rc::downgrade(&child));
+                    continue;
+                }
+                Line::Ls => continue,
+                Line::Dir(name) => FsEntry::Folder(name.into(), vec![], OnceCell::new()),
+                Line::File(name, size) => FsEntry::File(name.into(), size),
+            };
+            let is_dir = matches!(entry, FsEntry::Folder(..));
+            let child = Rc::new(RefCell::new(entry));
+            cwd.borrow_mut().children_mut().push(Rc::clone(&child));
+            if is_dir {
+                dirs.push(child);
+            }
+        }
+        FileTree { root, dirs }
+    }
+
+    pub fn part_one(tree: &FileTree) -> Option<u64> {
+        Some(
+            tree.dirs
+                .iter()
+                .map(|dir| dir.borrow().size())
+                .filter(|&size| size <= 100000)
+                .sum(),
+        )
+    }
+
+    pub fn part_two(tree: &FileTree) -> Option<u64> {
+        let to_free = 30000000 - (70000000 - tree.root.borrow().size());
+        tree.dirs
+            .iter()
+            .map(|dir| dir.borrow().size())
+            .filter(|&size| size >= to_free)
+            .min()
     }
 }
@@ -1,114 +1,59 @@
-#[derive(Clone, Copy, Debug)]
-struct Tree(u8);
+use advent_of_code::helpers::{grid, Direction, Grid};
 
-#[derive(Debug)]
-struct Grid {
-    width: usize,
-    trees: Vec<Tree>,
+/// Heights of the trees, from 0 to 9.
+pub fn parse(input: &str) -> Result<Grid<u8>, grid::ParseError> {
+    Grid::parse(input, |ch| ch.to_digit(10).map(|height| height as u8))
 }
 
-impl Grid {
-    fn new(input: &str) -> Self {
-        let mut width = 0;
-
-        let mut bytes = input.bytes();
-
-        let mut trees: Vec<_> = bytes
-            .by_ref()
-            .take_while(|&ch| ch != b'\n')
-            .inspect(|_| width += 1)
-            .map(Tree)
-            .collect();
-        assert_ne!(width, 0);
-
-        trees.extend(bytes.filter(|&ch| ch != b'\n').map(Tree));
+/// Whether no tree between the one at `pos` and the edge of the grid in `direction` is as
+/// tall as it.
+fn visible_from(grid: &Grid<u8>, pos: (usize, usize), direction: Direction) -> bool {
+    let height = grid[pos];
+    grid.ray(pos, direction).all(|(_, &other)| other < height)
+}
 
-        Grid { width, trees }
+/// Number of trees that can be seen from the tree at `pos` looking in `direction`, up to and
+/// including the first one that is at least as tall.
+fn viewing_distance(grid: &Grid<u8>, pos: (usize, usize), direction: Direction) -> usize {
+    let height = grid[pos];
+    let mut distance = 0;
+    for (_, &other) in grid.ray(pos, direction) {
+        distance += 1;
+        if other >= height {
+            break;
+        }
     }
+    distance
 }
 
-pub fn part_one(input: &str) -> Option<usize> {
-    let Grid { trees, width } = Grid::new(input);
-
+pub fn part_one(grid: &Grid<u8>) -> Option<usize> {
     Some(
-        trees[..(trees.len() - width)]
-            .iter()
-            .enumerate()
-            .filter(|&(idx, Tree(height))| {
-                let col = idx % width;
-                let row_start = idx - col;
-                let row_end = row_start + width;
-
-                let Some(_) = (trees[col..idx])
-                .iter()
-                .step_by(width)
-                .rev()
-                    .find(|t| t.0.ge(height)) else { return  true};
-
-                let Some(_) = (trees[(idx + width)..])
-                    .iter()
-                    .step_by(width)
-                    .find(|t| t.0.ge(height)) else { return  true};
-
-                let Some(_) = (trees[row_start..idx]).iter()
-                .rev().find(|t| t.0.ge(height)) else { return  true};
-
-                let Some(_) = (trees[(idx + 1)..row_end]).iter().find(|t| t.0.ge(height)) else { return  true};
-
-                false
+        grid.iter()
+            .filter(|&(pos, _)| {
+                Direction::ORTHOGONAL
+                    .into_iter()
+                    .any(|direction| visible_from(grid, pos, direction))
             })
-            .count()  + width,
+            .count(),
     )
 }
 
-pub fn part_two(input: &str) -> Option<usize> {
-    let Grid { trees, width } = Grid::new(input);
-    let rows = trees.len() / width;
-
-    trees[..(trees.len() - width)]
-        .iter()
-        .enumerate()
-        .map(|(idx, Tree(height))| {
-            let col = idx % width;
-            let row_start = idx - col;
-            let row_end = row_start + width;
-
-            let trees_above = idx / width;
-            let trees_below = rows - trees_above - 1;
-
-            let top = (trees[col..idx])
-                .iter()
-                .step_by(width)
-                .rev()
-                .position(|t| t.0.ge(height))
-                .map_or(trees_above, |p| p + 1);
-
-            let bottom = (trees[(idx + width)..])
-                .iter()
-                .step_by(width)
-                .position(|t| t.0.ge(height))
-                .map_or(trees_below, |p| p + 1);
-
-            let left = (trees[row_start..idx])
-                .iter()
-                .rev()
-                .position(|t| t.0.ge(height))
-                .map_or(col, |p| p + 1);
-
-            let right = (trees[(idx + 1)..row_end])
-                .iter()
-                .position(|t| t.0.ge(height))
-                .map_or(width - (col + 1), |p| p + 1);
-
-            top * bottom * left * right
+pub fn part_two(grid: &Grid<u8>) -> Option<usize> {
+    grid.iter()
+        .map(|(pos, _)| {
+            Direction::ORTHOGONAL
+                .into_iter()
+                .map(|direction| viewing_distance(grid, pos, direction))
+                .product()
         })
         .max()
 }
 
 fn main() {
     let input = &advent_of_code::read_file("inputs", 8);
-    advent_of_code::solve!(1, part_one, input);
-    advent_of_code::solve!(2, part_two, input);
+    let grid = advent_of_code::unwrap_or_exit(advent_of_code::parse!(parse, input));
+    advent_of_code::solve!(1, part_one, &grid);
+    advent_of_code::solve!(2, part_two, &grid);
 }
 
 #[cfg(test)]
@@ -118,12 +63,12 @@ mod tests {
     #[test]
     fn test_part_one() {
         let input = advent_of_code::read_file("examples", 8);
-        dbg!(part_one(&input));
+        assert_eq!(part_one(&parse(&input).unwrap()), Some(21));
     }
 
     #[test]
     fn test_part_two() {
         let input = advent_of_code::read_file("examples", 8);
-        assert_eq!(part_two(&input), Some(8));
+        assert_eq!(part_two(&parse(&input).unwrap()), Some(8));
     }
 }
@@ -0,0 +1,61 @@
+use std::fs::File;
+use std::io::{self, Write};
+use std::num::NonZeroUsize;
+use std::process;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+struct Args {
+    day: u8,
+    seed: Option<u64>,
+    size: NonZeroUsize,
+    output: Option<String>,
+}
+
+fn parse_args() -> Result<Args, pico_args::Error> {
+    let mut args = pico_args::Arguments::from_env();
+    Ok(Args {
+        day: args.free_from_str()?,
+        seed: args.opt_value_from_str(["-s", "--seed"])?,
+        size: args
+            .opt_value_from_str(["-n", "--size"])?
+            .unwrap_or(NonZeroUsize::new(1000).unwrap()),
+        output: args.opt_value_from_str(["-o", "--output"])?,
+    })
+}
+
+fn main() {
+    let args = match parse_args() {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("Failed to process arguments: {e}");
+            process::exit(1);
+        }
+    };
+
+    let seed = args.seed.unwrap_or_else(|| {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_nanos() as u64
+    });
+
+    let Some(input) = advent_of_code::generators::generate(args.day, seed, args.size.get()) else {
+        eprintln!("No generator for day {}.", args.day);
+        process::exit(1);
+    };
+
+    let result = match &args.output {
+        Some(path) => File::create(path).and_then(|mut f| f.write_all(input.as_bytes())),
+        None => io::stdout().write_all(input.as_bytes()),
+    };
+
+    if let Err(e) = result {
+        eprintln!("Failed to write input: {e}");
+        process::exit(1);
+    }
+
+    eprintln!(
+        "🎄 Generated input for day {:02} (seed: {}, size: {}).",
+        args.day, seed, args.size
+    );
+}
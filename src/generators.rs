@@ -0,0 +1,123 @@
+/*
+ * Input generators for stress testing and fuzzing the solutions.
+ * Every day has a module with a `generate(seed, size)` function that returns a valid puzzle input.
+ * Example import from this file: `use advent_of_code::generators::day07;`.
+ */
+use std::ops::RangeInclusive;
+
+pub mod day01;
+pub mod day02;
+pub mod day03;
+pub mod day04;
+pub mod day05;
+pub mod day06;
+pub mod day07;
+pub mod day08;
+
+/// Generates an input for `day` from `seed`, or `None` if there is no generator for that day.
+/// What `size` means depends on the day, e.g. the number of elves or the width of a grid.
+/// A `size` of 0 makes some days return an empty input, which is not a valid puzzle input.
+pub fn generate(day: u8, seed: u64, size: usize) -> Option<String> {
+    let generator = match day {
+        1 => day01::generate,
+        2 => day02::generate,
+        3 => day03::generate,
+        4 => day04::generate,
+        5 => day05::generate,
+        6 => day06::generate,
+        7 => day07::generate,
+        8 => day08::generate,
+        _ => return None,
+    };
+    Some(generator(seed, size))
+}
+
+/// Small, deterministic pseudo random number generator (SplitMix64).
+/// Good enough for test inputs, not for anything else.
+#[derive(Clone, Debug)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Uniform value in `0..n`. Panics if `n` is zero.
+    pub fn below(&mut self, n: usize) -> usize {
+        assert_ne!(n, 0, "Cannot pick from an empty range");
+        ((self.next_u64() as u128 * n as u128) >> 64) as usize
+    }
+
+    /// Uniform value in `range`. Panics if the range is empty.
+    pub fn range(&mut self, range: RangeInclusive<u64>) -> u64 {
+        let (start, end) = range.into_inner();
+        assert!(start <= end, "Cannot pick from an empty range");
+        match (end - start).checked_add(1) {
+            Some(len) => start + ((self.next_u64() as u128 * len as u128) >> 64) as u64,
+            None => self.next_u64(),
+        }
+    }
+
+    pub fn choose<'a, T>(&mut self, items: &'a [T]) -> &'a T {
+        &items[self.below(items.len())]
+    }
+
+    pub fn shuffle<T>(&mut self, items: &mut [T]) {
+        for i in (1..items.len()).rev() {
+            items.swap(i, self.below(i + 1));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rng_is_deterministic() {
+        let a: Vec<_> = std::iter::repeat_with({
+            let mut rng = Rng::new(42);
+            move || rng.next_u64()
+        })
+        .take(8)
+        .collect();
+        let b: Vec<_> = std::iter::repeat_with({
+            let mut rng = Rng::new(42);
+            move || rng.next_u64()
+        })
+        .take(8)
+        .collect();
+        assert_eq!(a, b);
+    }
+
+    #[test]
+    fn test_rng_range_bounds() {
+        let mut rng = Rng::new(7);
+        for _ in 0..1000 {
+            assert!((3..=5).contains(&rng.range(3..=5)));
+            assert!(rng.below(3) < 3);
+        }
+        assert_eq!(rng.range(9..=9), 9);
+        rng.range(0..=u64::MAX);
+    }
+
+    #[test]
+    fn test_generate_every_day() {
+        for day in 1..=8 {
+            let input = generate(day, 1, 20).unwrap();
+            assert!(!input.is_empty());
+            assert_eq!(input, generate(day, 1, 20).unwrap());
+        }
+        assert_eq!(generate(25, 1, 20), None);
+    }
+}
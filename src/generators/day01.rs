@@ -0,0 +1,32 @@
+//! Calorie lists: `size` elves carrying between one and ten snacks each.
+use super::Rng;
+use std::fmt::Write;
+
+pub fn generate(seed: u64, size: usize) -> String {
+    let mut rng = Rng::new(seed);
+    let mut out = String::new();
+
+    for elf in 0..size {
+        if elf > 0 {
+            out.push('\n');
+        }
+        for _ in 0..rng.range(1..=10) {
+            writeln!(out, "{}", rng.range(1000..=60000)).unwrap();
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_groups() {
+        let input = generate(3, 50);
+        assert_eq!(input.split("\n\n").count(), 50);
+        assert!(input
+            .lines()
+            .all(|line| line.is_empty() || line.parse::<u32>().is_ok()));
+    }
+}
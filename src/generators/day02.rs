@@ -0,0 +1,29 @@
+//! Strategy guides: `size` rounds of `A-C` against `X-Z`.
+use super::Rng;
+
+pub fn generate(seed: u64, size: usize) -> String {
+    let mut rng = Rng::new(seed);
+    let mut out = String::with_capacity(size * 4);
+
+    for _ in 0..size {
+        out.push(*rng.choose(&['A', 'B', 'C']));
+        out.push(' ');
+        out.push(*rng.choose(&['X', 'Y', 'Z']));
+        out.push('\n');
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rounds() {
+        let input = generate(3, 100);
+        assert_eq!(input.len(), 400);
+        assert!(input
+            .lines()
+            .all(|line| matches!(line.as_bytes(), [b'A'..=b'C', b' ', b'X'..=b'Z'])));
+    }
+}
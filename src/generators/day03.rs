@@ -0,0 +1,82 @@
+//! Rucksacks: `size` rounded up to whole groups of three.
+//!
+//! The compartments of every rucksack share exactly one item type, and every group of three
+//! shares exactly one badge. Each rucksack draws its other items from its own third of the
+//! alphabet, and each compartment from its own half of that third, to keep both unique.
+use super::Rng;
+
+const ITEMS: &[u8; 52] = b"abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ";
+
+fn rucksack(rng: &mut Rng, badge: u8, own: &[u8]) -> Vec<u8> {
+    let shared = if rng.below(4) == 0 {
+        badge
+    } else {
+        *rng.choose(own)
+    };
+
+    let mut rest: Vec<u8> = own.iter().copied().filter(|&b| b != shared).collect();
+    if shared != badge {
+        rest.push(badge);
+    }
+    rng.shuffle(&mut rest);
+    let (head_items, tail_items) = rest.split_at(rest.len() / 2);
+
+    let half_len = rng.range(2..=16) as usize;
+    let mut fill = |items: &[u8]| {
+        let mut half = vec![shared];
+        half.extend((1..half_len).map(|_| *rng.choose(items)));
+        if items.contains(&badge) {
+            half[1] = badge;
+        }
+        rng.shuffle(&mut half);
+        half
+    };
+
+    let mut line = fill(head_items);
+    line.extend(fill(tail_items));
+    line
+}
+
+pub fn generate(seed: u64, size: usize) -> String {
+    let mut rng = Rng::new(seed);
+    let mut out = Vec::new();
+
+    for _ in 0..size.div_ceil(3) {
+        let mut items = *ITEMS;
+        rng.shuffle(&mut items);
+        let (&badge, others) = items.split_first().unwrap();
+
+        for own in others.chunks(others.len() / 3) {
+            out.extend(rucksack(&mut rng, badge, own));
+            out.push(b'\n');
+        }
+    }
+    String::from_utf8(out).unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn set(items: &[u8]) -> u64 {
+        items.iter().fold(0, |acc, b| {
+            acc | 1 << ITEMS.iter().position(|i| i == b).unwrap()
+        })
+    }
+
+    #[test]
+    fn test_unique_common_items() {
+        let input = generate(11, 300);
+        let lines: Vec<_> = input.lines().map(str::as_bytes).collect();
+        assert_eq!(lines.len(), 300);
+
+        for line in &lines {
+            let (head, tail) = line.split_at(line.len() / 2);
+            assert_eq!((set(head) & set(tail)).count_ones(), 1);
+        }
+        for group in lines.chunks(3) {
+            let common = group.iter().fold(u64::MAX, |acc, line| acc & set(line));
+            assert_eq!(common.count_ones(), 1);
+        }
+    }
+}
@@ -0,0 +1,35 @@
+//! Section assignments: `size` pairs of ranges within sections 1 to 99.
+use super::Rng;
+use std::fmt::Write;
+
+fn range(rng: &mut Rng) -> (u64, u64) {
+    let start = rng.range(1..=99);
+    (start, rng.range(start..=99))
+}
+
+pub fn generate(seed: u64, size: usize) -> String {
+    let mut rng = Rng::new(seed);
+    let mut out = String::new();
+
+    for _ in 0..size {
+        let (a, b) = range(&mut rng);
+        let (c, d) = range(&mut rng);
+        writeln!(out, "{a}-{b},{c}-{d}").unwrap();
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pairs() {
+        let input = generate(5, 100);
+        assert_eq!(input.lines().count(), 100);
+        for line in input.lines() {
+            let nums: Vec<u8> = line.split([',', '-']).map(|n| n.parse().unwrap()).collect();
+            assert!(nums[0] <= nums[1] && nums[2] <= nums[3]);
+        }
+    }
+}
@@ -0,0 +1,80 @@
+//! Crate stacks: a drawing of nine stacks followed by `size` valid moves.
+use super::Rng;
+use std::fmt::Write;
+
+const STACKS: usize = 9;
+
+/// Draws the stacks the way the puzzle input does: top row first, every row padded to the
+/// width of the stack numbers below them.
+pub fn render_drawing<T: Copy + Into<char>>(stacks: &[Vec<T>]) -> String {
+    let height = stacks.iter().map(Vec::len).max().unwrap_or(0);
+    let mut out = String::new();
+
+    for level in (0..height).rev() {
+        let row: Vec<_> = stacks
+            .iter()
+            .map(|stack| match stack.get(level) {
+                Some(&label) => format!("[{}]", label.into()),
+                None => "   ".to_string(),
+            })
+            .collect();
+        writeln!(out, "{}", row.join(" ")).unwrap();
+    }
+
+    let labels: Vec<_> = (1..=stacks.len()).map(|n| format!("{n:^3}")).collect();
+    writeln!(out, "{}", labels.join(" ")).unwrap();
+    out
+}
+
+pub fn generate(seed: u64, size: usize) -> String {
+    let mut rng = Rng::new(seed);
+
+    let mut stacks: Vec<Vec<u8>> = (0..STACKS)
+        .map(|_| {
+            (0..rng.range(1..=8))
+                .map(|_| rng.range(b'A' as u64..=b'Z' as u64) as u8)
+                .collect()
+        })
+        .collect();
+
+    let mut out = render_drawing(&stacks);
+    out.push('\n');
+
+    for _ in 0..size {
+        let non_empty: Vec<_> = (0..STACKS).filter(|&i| !stacks[i].is_empty()).collect();
+        let from = *rng.choose(&non_empty);
+        let to = (from + 1 + rng.below(STACKS - 1)) % STACKS;
+        let count = rng.range(1..=stacks[from].len().min(10) as u64) as usize;
+
+        let remaining = stacks[from].len() - count;
+        let moved = stacks[from].split_off(remaining);
+        stacks[to].extend(moved);
+
+        writeln!(out, "move {} from {} to {}", count, from + 1, to + 1).unwrap();
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_drawing() {
+        let drawing = render_drawing(&[vec![b'Z', b'N'], vec![b'M', b'C', b'D'], vec![b'P']]);
+        assert_eq!(
+            drawing,
+            "    [D]    \n[N] [C]    \n[Z] [M] [P]\n 1   2   3 \n"
+        );
+
+        let stacks: Vec<Vec<char>> = (0..10).map(|_| vec!['A']).collect();
+        assert!(render_drawing(&stacks).ends_with("  9  10 \n"));
+    }
+
+    #[test]
+    fn test_moves() {
+        let input = generate(9, 40);
+        assert_eq!(input.lines().filter(|l| l.starts_with("move")).count(), 40);
+        assert!(input.lines().next().unwrap().len() == STACKS * 4 - 1);
+    }
+}
@@ -0,0 +1,40 @@
+//! Datastream buffers: `size` lowercase letters with a start-of-message marker in the second half.
+use super::Rng;
+
+const MARKER_LEN: usize = 14;
+
+pub fn generate(seed: u64, size: usize) -> String {
+    let size = size.max(MARKER_LEN);
+    let mut rng = Rng::new(seed);
+
+    let mut bytes: Vec<u8> = (0..size)
+        .map(|_| rng.range(b'a' as u64..=b'z' as u64) as u8)
+        .collect();
+
+    let mut letters: Vec<u8> = (b'a'..=b'z').collect();
+    rng.shuffle(&mut letters);
+    let last_start = size - MARKER_LEN;
+    let first_start = (size / 2).min(last_start);
+    let start = first_start + rng.below(last_start - first_start + 1);
+    bytes[start..start + MARKER_LEN].copy_from_slice(&letters[..MARKER_LEN]);
+
+    bytes.push(b'\n');
+    String::from_utf8(bytes).unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_has_marker() {
+        for size in [0, 14, 15, 1000] {
+            let input = generate(size as u64, size);
+            let has_marker = input
+                .as_bytes()
+                .windows(MARKER_LEN)
+                .any(|w| (1..w.len()).all(|i| !w[..i].contains(&w[i])));
+            assert!(has_marker);
+        }
+    }
+}
@@ -0,0 +1,151 @@
+//! Terminal transcripts: a random tree of `size` directories explored depth-first with `cd` and `ls`.
+//!
+//! File sizes are scaled so the total disk usage lands between 40000000 and 70000000,
+//! which keeps part two solvable with its fixed disk and update sizes. To leave room for the
+//! rounding of every file, there are at most [`MAX_FILES`] files however large `size` is.
+use super::Rng;
+use std::fmt::Write;
+
+/// Upper bound on the number of files in a transcript.
+pub const MAX_FILES: usize = 1_000_000;
+
+struct Dir {
+    name: String,
+    subdirs: Vec<usize>,
+    files: Vec<(String, u64)>,
+}
+
+fn name(rng: &mut Rng, taken: &mut Vec<String>) -> String {
+    loop {
+        let len = rng.range(1..=8);
+        let mut name: String = (0..len)
+            .map(|_| rng.range(b'a' as u64..=b'z' as u64) as u8 as char)
+            .collect();
+        if rng.below(3) == 0 {
+            name.push('.');
+            name.extend((0..3).map(|_| rng.range(b'a' as u64..=b'z' as u64) as u8 as char));
+        }
+        if !taken.contains(&name) {
+            taken.push(name.clone());
+            return name;
+        }
+    }
+}
+
+fn build_tree(rng: &mut Rng, size: usize) -> Vec<Dir> {
+    let mut names: Vec<Vec<String>> = vec![vec![]];
+    let mut dirs = vec![Dir {
+        name: "/".to_string(),
+        subdirs: vec![],
+        files: vec![],
+    }];
+
+    for idx in 1..size.max(1) {
+        // Prefer recent directories as parents to get some depth.
+        let parent = idx - 1 - rng.below(idx.min(8));
+        dirs.push(Dir {
+            name: name(rng, &mut names[parent]),
+            subdirs: vec![],
+            files: vec![],
+        });
+        names.push(vec![]);
+        dirs[parent].subdirs.push(idx);
+    }
+
+    let mut file_count = 0;
+    for (idx, dir) in dirs.iter_mut().enumerate() {
+        let count =
+            (if idx == 0 { 1 } else { 0 } + rng.range(0..=5) as usize).min(MAX_FILES - file_count);
+        file_count += count;
+        for _ in 0..count {
+            let file_name = name(rng, &mut names[idx]);
+            dir.files.push((file_name, rng.range(1..=300000)));
+        }
+    }
+
+    let total: u64 = dirs.iter().flat_map(|d| &d.files).map(|f| f.1).sum();
+    // Rounding down and then up to at least 1 adds at most 1 per file, keep room for that.
+    let target = rng.range(40000001..=69999999 - file_count as u64);
+    let mut scaled_total = 0;
+    for (_, size) in dirs.iter_mut().flat_map(|d| &mut d.files) {
+        *size = (*size as u128 * target as u128 / total as u128).max(1) as u64;
+        scaled_total += *size;
+    }
+    // Integer division rounds down; put the remainder into the first file of the root.
+    dirs[0].files[0].1 += target.saturating_sub(scaled_total);
+
+    dirs
+}
+
+pub fn generate(seed: u64, size: usize) -> String {
+    let mut rng = Rng::new(seed);
+    let dirs = build_tree(&mut rng, size);
+    let mut out = String::new();
+
+    // Explicit stack instead of recursion: random trees can get deep.
+    enum Step {
+        Enter(usize),
+        Leave,
+    }
+    let mut steps = vec![Step::Enter(0)];
+
+    while let Some(step) = steps.pop() {
+        match step {
+            Step::Enter(idx) => {
+                let dir = &dirs[idx];
+                writeln!(out, "$ cd {}", dir.name).unwrap();
+                writeln!(out, "$ ls").unwrap();
+
+                let mut entries: Vec<String> = dir
+                    .subdirs
+                    .iter()
+                    .map(|&sub| format!("dir {}", dirs[sub].name))
+                    .chain(
+                        dir.files
+                            .iter()
+                            .map(|(name, size)| format!("{size} {name}")),
+                    )
+                    .collect();
+                rng.shuffle(&mut entries);
+                for entry in entries {
+                    writeln!(out, "{entry}").unwrap();
+                }
+
+                if idx != 0 {
+                    steps.push(Step::Leave);
+                }
+                steps.extend(dir.subdirs.iter().rev().map(|&sub| Step::Enter(sub)));
+            }
+            Step::Leave => writeln!(out, "$ cd ..").unwrap(),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_transcript() {
+        let input = generate(4, 200);
+        assert!(input.starts_with("$ cd /\n$ ls\n"));
+        assert_eq!(input.lines().filter(|l| l.starts_with("dir ")).count(), 199);
+
+        let total: u64 = input
+            .lines()
+            .filter_map(|l| l.split_once(' ')?.0.parse::<u64>().ok())
+            .sum();
+        assert!((40000001..70000000).contains(&total));
+    }
+
+    #[test]
+    fn test_total_with_many_files() {
+        let mut rng = Rng::new(7);
+        let dirs = build_tree(&mut rng, MAX_FILES);
+        let files: Vec<u64> = dirs.iter().flat_map(|d| &d.files).map(|f| f.1).collect();
+        assert_eq!(files.len(), MAX_FILES);
+        assert!(files.iter().all(|&size| size >= 1));
+        assert!((40000001..70000000).contains(&files.iter().sum::<u64>()));
+    }
+}
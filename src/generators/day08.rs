@@ -0,0 +1,27 @@
+//! Tree maps: a `size` by `size` grid of tree heights.
+use super::Rng;
+
+pub fn generate(seed: u64, size: usize) -> String {
+    let mut rng = Rng::new(seed);
+    let mut out = String::with_capacity(size * (size + 1));
+
+    for _ in 0..size {
+        out.extend((0..size).map(|_| char::from(b'0' + rng.below(10) as u8)));
+        out.push('\n');
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_square() {
+        let input = generate(1, 30);
+        assert_eq!(input.lines().count(), 30);
+        assert!(input
+            .lines()
+            .all(|l| l.len() == 30 && l.bytes().all(|b| b.is_ascii_digit())));
+    }
+}
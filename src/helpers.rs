@@ -2,3 +2,12 @@
  * Use this file if you want to extract helpers from your solutions.
  * Example import from this file: `use advent_of_code::helpers::example_fn;`.
  */
+pub mod byte_set;
+pub mod grid;
+pub mod interval;
+pub mod top_k;
+
+pub use byte_set::ByteSet;
+pub use grid::{Direction, Grid};
+pub use interval::Interval;
+pub use top_k::{top_k, TopK};
@@ -0,0 +1,228 @@
+//! Sets of bytes stored as a 256-bit bitmap.
+use std::ops::{BitAnd, BitAndAssign, BitOr, BitOrAssign};
+
+/// Set of byte values, bit `b` of the bitmap is set if `b` is in the set.
+/// Small enough to be `Copy`, so set operations take and return values.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct ByteSet {
+    words: [u64; 4],
+}
+
+impl ByteSet {
+    pub const EMPTY: ByteSet = ByteSet { words: [0; 4] };
+    pub const ALL: ByteSet = ByteSet { words: [!0; 4] };
+
+    pub fn new() -> Self {
+        Self::EMPTY
+    }
+
+    /// Set of all bytes in `bytes`.
+    ///
+    /// Each byte is masked into all four words instead of indexing the word it belongs to,
+    /// which keeps the loop free of branches and stores so that it can be vectorized.
+    pub fn from_bytes(bytes: &[u8]) -> Self {
+        let mut words = [0_u64; 4];
+        for &byte in bytes {
+            let bit = 1 << (byte & 63);
+            let word = byte >> 6;
+            for (idx, acc) in words.iter_mut().enumerate() {
+                *acc |= bit & 0_u64.wrapping_sub((word == idx as u8) as u64);
+            }
+        }
+        Self { words }
+    }
+
+    fn position(byte: u8) -> (usize, u64) {
+        ((byte >> 6) as usize, 1 << (byte & 63))
+    }
+
+    /// Adds `byte` to the set, returns whether it was not in the set before.
+    pub fn insert(&mut self, byte: u8) -> bool {
+        let (word, bit) = Self::position(byte);
+        let inserted = self.words[word] & bit == 0;
+        self.words[word] |= bit;
+        inserted
+    }
+
+    /// Removes `byte` from the set, returns whether it was in the set before.
+    pub fn remove(&mut self, byte: u8) -> bool {
+        let (word, bit) = Self::position(byte);
+        let removed = self.words[word] & bit != 0;
+        self.words[word] &= !bit;
+        removed
+    }
+
+    pub fn contains(&self, byte: u8) -> bool {
+        let (word, bit) = Self::position(byte);
+        self.words[word] & bit != 0
+    }
+
+    pub fn intersection(&self, other: &Self) -> Self {
+        *self & *other
+    }
+
+    pub fn union(&self, other: &Self) -> Self {
+        *self | *other
+    }
+
+    /// Number of bytes in the set.
+    pub fn len(&self) -> usize {
+        self.words
+            .iter()
+            .map(|word| word.count_ones() as usize)
+            .sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.words == [0; 4]
+    }
+
+    /// The bytes in the set, in ascending order.
+    pub fn iter(&self) -> Iter {
+        Iter {
+            words: self.words,
+            word: 0,
+        }
+    }
+}
+
+impl BitAnd for ByteSet {
+    type Output = ByteSet;
+
+    fn bitand(mut self, rhs: Self) -> Self::Output {
+        self &= rhs;
+        self
+    }
+}
+
+impl BitAndAssign for ByteSet {
+    fn bitand_assign(&mut self, rhs: Self) {
+        self.words
+            .iter_mut()
+            .zip(rhs.words)
+            .for_each(|(word, other)| *word &= other);
+    }
+}
+
+impl BitOr for ByteSet {
+    type Output = ByteSet;
+
+    fn bitor(mut self, rhs: Self) -> Self::Output {
+        self |= rhs;
+        self
+    }
+}
+
+impl BitOrAssign for ByteSet {
+    fn bitor_assign(&mut self, rhs: Self) {
+        self.words
+            .iter_mut()
+            .zip(rhs.words)
+            .for_each(|(word, other)| *word |= other);
+    }
+}
+
+impl From<&[u8]> for ByteSet {
+    fn from(bytes: &[u8]) -> Self {
+        Self::from_bytes(bytes)
+    }
+}
+
+impl FromIterator<u8> for ByteSet {
+    fn from_iter<I: IntoIterator<Item = u8>>(iter: I) -> Self {
+        let mut set = Self::new();
+        set.extend(iter);
+        set
+    }
+}
+
+impl Extend<u8> for ByteSet {
+    fn extend<I: IntoIterator<Item = u8>>(&mut self, iter: I) {
+        iter.into_iter().for_each(|byte| {
+            self.insert(byte);
+        });
+    }
+}
+
+/// Iterator over the bytes of a [`ByteSet`], in ascending order.
+#[derive(Clone, Debug)]
+pub struct Iter {
+    words: [u64; 4],
+    word: usize,
+}
+
+impl Iterator for Iter {
+    type Item = u8;
+
+    fn next(&mut self) -> Option<u8> {
+        while self.word < 4 {
+            let bits = &mut self.words[self.word];
+            if *bits != 0 {
+                let byte = self.word as u32 * 64 + bits.trailing_zeros();
+                *bits &= *bits - 1;
+                return Some(byte as u8);
+            }
+            self.word += 1;
+        }
+        None
+    }
+}
+
+impl IntoIterator for ByteSet {
+    type Item = u8;
+    type IntoIter = Iter;
+
+    fn into_iter(self) -> Iter {
+        self.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_insert_remove() {
+        let mut set = ByteSet::new();
+        assert!(set.is_empty());
+        assert!(set.insert(b'a'));
+        assert!(!set.insert(b'a'));
+        assert!(set.insert(255));
+        assert!(set.insert(0));
+        assert!(set.contains(b'a') && set.contains(255) && set.contains(0));
+        assert!(!set.contains(b'b'));
+        assert_eq!(set.len(), 3);
+        assert!(set.remove(b'a'));
+        assert!(!set.remove(b'a'));
+        assert_eq!(set.iter().collect::<Vec<_>>(), [0, 255]);
+        assert_eq!(ByteSet::ALL.len(), 256);
+    }
+
+    #[test]
+    fn test_set_operations() {
+        let a = ByteSet::from_bytes(b"vJrwpWtwJgWr");
+        let b = ByteSet::from_bytes(b"hcsFMMfFFhFp");
+        assert_eq!(a.intersection(&b).iter().collect::<Vec<_>>(), b"p");
+        assert_eq!(a.union(&b).len(), 14);
+        assert_eq!(a & ByteSet::ALL, a);
+        assert_eq!(a | ByteSet::EMPTY, a);
+    }
+
+    #[test]
+    fn test_from_bytes_matches_insert() {
+        let mut rng = crate::generators::Rng::new(3);
+        for _ in 0..100 {
+            let bytes: Vec<u8> = (0..rng.below(300))
+                .map(|_| rng.range(0..=255) as u8)
+                .collect();
+            let set = ByteSet::from_bytes(&bytes);
+            assert_eq!(set, bytes.iter().copied().collect());
+
+            let mut expected = bytes.clone();
+            expected.sort_unstable();
+            expected.dedup();
+            assert_eq!(set.iter().collect::<Vec<_>>(), expected);
+            assert_eq!(set.len(), expected.len());
+        }
+    }
+}
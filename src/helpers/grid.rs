@@ -0,0 +1,345 @@
+//! Rectangular grids of cells addressed by `(x, y)`, with `y` growing downwards.
+use std::{
+    fmt::Display,
+    ops::{Index, IndexMut},
+};
+
+/// One of the eight directions from a cell to its neighbours.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Direction {
+    Up,
+    Down,
+    Left,
+    Right,
+    UpLeft,
+    UpRight,
+    DownLeft,
+    DownRight,
+}
+
+impl Direction {
+    /// Directions that share an edge with the cell.
+    pub const ORTHOGONAL: [Direction; 4] = [
+        Direction::Up,
+        Direction::Down,
+        Direction::Left,
+        Direction::Right,
+    ];
+
+    /// Directions that share an edge or a corner with the cell.
+    pub const ALL: [Direction; 8] = [
+        Direction::Up,
+        Direction::Down,
+        Direction::Left,
+        Direction::Right,
+        Direction::UpLeft,
+        Direction::UpRight,
+        Direction::DownLeft,
+        Direction::DownRight,
+    ];
+
+    /// Change of `(x, y)` for one step in this direction.
+    pub fn delta(self) -> (isize, isize) {
+        match self {
+            Direction::Up => (0, -1),
+            Direction::Down => (0, 1),
+            Direction::Left => (-1, 0),
+            Direction::Right => (1, 0),
+            Direction::UpLeft => (-1, -1),
+            Direction::UpRight => (1, -1),
+            Direction::DownLeft => (-1, 1),
+            Direction::DownRight => (1, 1),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum ErrorKind {
+    Empty,
+    RaggedRow { expected: usize, found: usize },
+    InvalidCell { column: usize, found: char },
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct ParseError {
+    line: usize,
+    kind: ErrorKind,
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "line {}: ", self.line)?;
+        match &self.kind {
+            ErrorKind::Empty => write!(f, "expected at least one row"),
+            ErrorKind::RaggedRow { expected, found } => {
+                write!(f, "expected {expected} cells, found {found}")
+            }
+            ErrorKind::InvalidCell { column, found } => {
+                write!(f, "column {column}: unexpected {found:?}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// Cells stored row by row in one `Vec`.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Grid<T> {
+    width: usize,
+    height: usize,
+    cells: Vec<T>,
+}
+
+impl<T> Grid<T> {
+    /// Grid of `width` columns made of `cells` in row order. Panics if `width` is zero or
+    /// doesn't divide the number of cells.
+    pub fn new(width: usize, cells: Vec<T>) -> Self {
+        assert!(width > 0, "Grid must have at least one column");
+        assert!(
+            cells.len().is_multiple_of(width),
+            "Grid cells must fill every row"
+        );
+        Self {
+            width,
+            height: cells.len() / width,
+            cells,
+        }
+    }
+
+    /// Parses a map with one character per cell and one line per row, all rows as wide as
+    /// the first. `cell` rejects a character by returning `None`.
+    pub fn parse(input: &str, mut cell: impl FnMut(char) -> Option<T>) -> Result<Self, ParseError> {
+        let mut width = None;
+        let mut cells = Vec::with_capacity(input.len());
+
+        for (line, line_no) in input.lines().zip(1..) {
+            let error = |kind| ParseError {
+                line: line_no,
+                kind,
+            };
+            let row_start = cells.len();
+            for (found, column) in line.chars().zip(1..) {
+                cells.push(
+                    cell(found).ok_or_else(|| error(ErrorKind::InvalidCell { column, found }))?,
+                );
+            }
+
+            let found = cells.len() - row_start;
+            match *width.get_or_insert(found) {
+                0 => return Err(error(ErrorKind::Empty)),
+                expected if expected != found => {
+                    return Err(error(ErrorKind::RaggedRow { expected, found }))
+                }
+                _ => {}
+            }
+        }
+
+        match width {
+            Some(width) => Ok(Self::new(width, cells)),
+            None => Err(ParseError {
+                line: 1,
+                kind: ErrorKind::Empty,
+            }),
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn contains(&self, (x, y): (usize, usize)) -> bool {
+        x < self.width && y < self.height
+    }
+
+    pub fn get(&self, pos: (usize, usize)) -> Option<&T> {
+        self.contains(pos)
+            .then(|| &self.cells[pos.1 * self.width + pos.0])
+    }
+
+    pub fn get_mut(&mut self, pos: (usize, usize)) -> Option<&mut T> {
+        if self.contains(pos) {
+            Some(&mut self.cells[pos.1 * self.width + pos.0])
+        } else {
+            None
+        }
+    }
+
+    /// Row `y`, from left to right. Panics if `y` is out of bounds.
+    pub fn row(&self, y: usize) -> &[T] {
+        &self.cells[y * self.width..][..self.width]
+    }
+
+    /// Column `x`, from top to bottom. Panics if `x` is out of bounds.
+    pub fn column(&self, x: usize) -> impl DoubleEndedIterator<Item = &T> + '_ {
+        assert!(x < self.width, "Column {x} is out of bounds");
+        self.cells[x..].iter().step_by(self.width)
+    }
+
+    /// All rows, from top to bottom.
+    pub fn rows(&self) -> impl DoubleEndedIterator<Item = &[T]> + '_ {
+        self.cells.chunks_exact(self.width)
+    }
+
+    /// All columns, from left to right.
+    pub fn columns(
+        &self,
+    ) -> impl DoubleEndedIterator<Item = impl DoubleEndedIterator<Item = &T> + '_> + '_ {
+        (0..self.width).map(|x| self.column(x))
+    }
+
+    /// Every cell with its position, row by row.
+    pub fn iter(&self) -> impl Iterator<Item = ((usize, usize), &T)> + '_ {
+        let width = self.width;
+        self.cells
+            .iter()
+            .enumerate()
+            .map(move |(idx, cell)| ((idx % width, idx / width), cell))
+    }
+
+    /// Position one step from `pos` in `direction`, if that is still in the grid.
+    pub fn step(&self, (x, y): (usize, usize), direction: Direction) -> Option<(usize, usize)> {
+        let (dx, dy) = direction.delta();
+        let next = (x.checked_add_signed(dx)?, y.checked_add_signed(dy)?);
+        self.contains(next).then_some(next)
+    }
+
+    /// Positions of the cells sharing an edge with `pos`.
+    pub fn neighbours4(&self, pos: (usize, usize)) -> impl Iterator<Item = (usize, usize)> + '_ {
+        Direction::ORTHOGONAL
+            .into_iter()
+            .filter_map(move |direction| self.step(pos, direction))
+    }
+
+    /// Positions of the cells sharing an edge or a corner with `pos`.
+    pub fn neighbours8(&self, pos: (usize, usize)) -> impl Iterator<Item = (usize, usize)> + '_ {
+        Direction::ALL
+            .into_iter()
+            .filter_map(move |direction| self.step(pos, direction))
+    }
+
+    /// Cells from `pos` in `direction` up to the edge of the grid, `pos` itself excluded.
+    pub fn ray(&self, pos: (usize, usize), direction: Direction) -> Ray<'_, T> {
+        Ray {
+            grid: self,
+            pos,
+            direction,
+        }
+    }
+}
+
+impl<T> Index<(usize, usize)> for Grid<T> {
+    type Output = T;
+
+    fn index(&self, pos: (usize, usize)) -> &T {
+        self.get(pos)
+            .unwrap_or_else(|| panic!("Position {pos:?} is out of bounds"))
+    }
+}
+
+impl<T> IndexMut<(usize, usize)> for Grid<T> {
+    fn index_mut(&mut self, pos: (usize, usize)) -> &mut T {
+        self.get_mut(pos)
+            .unwrap_or_else(|| panic!("Position {pos:?} is out of bounds"))
+    }
+}
+
+/// Iterator over the cells in a straight line from a position, see [`Grid::ray`].
+#[derive(Clone, Debug)]
+pub struct Ray<'a, T> {
+    grid: &'a Grid<T>,
+    pos: (usize, usize),
+    direction: Direction,
+}
+
+impl<'a, T> Iterator for Ray<'a, T> {
+    type Item = ((usize, usize), &'a T);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.pos = self.grid.step(self.pos, self.direction)?;
+        Some((self.pos, &self.grid[self.pos]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn digits(input: &str) -> Result<Grid<u32>, ParseError> {
+        Grid::parse(input, |ch| ch.to_digit(10))
+    }
+
+    #[test]
+    fn test_parse() {
+        let grid = digits("123\n456\n").unwrap();
+        assert_eq!((grid.width(), grid.height()), (3, 2));
+        assert_eq!(grid, Grid::new(3, vec![1, 2, 3, 4, 5, 6]));
+
+        let error = |input| digits(input).unwrap_err().to_string();
+        assert_eq!(error(""), "line 1: expected at least one row");
+        assert_eq!(error("\n12\n"), "line 1: expected at least one row");
+        assert_eq!(error("12\n345\n"), "line 2: expected 2 cells, found 3");
+        assert_eq!(error("12\n3\n"), "line 2: expected 2 cells, found 1");
+        assert_eq!(error("12\n3x\n"), "line 2: column 2: unexpected 'x'");
+    }
+
+    #[test]
+    fn test_access() {
+        let mut grid = digits("123\n456\n").unwrap();
+        assert_eq!(grid.get((2, 1)), Some(&6));
+        assert_eq!(grid.get((3, 0)), None);
+        assert_eq!(grid.get((0, 2)), None);
+        assert_eq!(grid[(1, 0)], 2);
+        grid[(1, 0)] = 9;
+        assert_eq!(grid.row(0), [1, 9, 3]);
+        assert_eq!(grid.column(1).copied().collect::<Vec<_>>(), [9, 5]);
+        assert_eq!(
+            grid.rows().map(<[u32]>::to_vec).collect::<Vec<_>>(),
+            [[1, 9, 3], [4, 5, 6]]
+        );
+        assert_eq!(
+            grid.columns()
+                .map(|column| column.rev().copied().collect::<Vec<_>>())
+                .collect::<Vec<_>>(),
+            [[4, 1], [5, 9], [6, 3]]
+        );
+        assert_eq!(grid.iter().nth(4), Some(((1, 1), &5)));
+    }
+
+    #[test]
+    fn test_neighbours() {
+        let grid = digits("123\n456\n789\n").unwrap();
+        let mut corner: Vec<_> = grid.neighbours4((0, 0)).collect();
+        corner.sort_unstable();
+        assert_eq!(corner, [(0, 1), (1, 0)]);
+        assert_eq!(grid.neighbours8((0, 0)).count(), 3);
+        assert_eq!(grid.neighbours4((1, 1)).count(), 4);
+
+        let mut around: Vec<_> = grid.neighbours8((1, 1)).map(|pos| grid[pos]).collect();
+        around.sort_unstable();
+        assert_eq!(around, [1, 2, 3, 4, 6, 7, 8, 9]);
+    }
+
+    #[test]
+    fn test_rays() {
+        let grid = digits("123\n456\n789\n").unwrap();
+        let ray = |pos, direction| {
+            grid.ray(pos, direction)
+                .map(|(_, &cell)| cell)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(ray((0, 0), Direction::Right), [2, 3]);
+        assert_eq!(ray((0, 0), Direction::DownRight), [5, 9]);
+        assert_eq!(ray((2, 2), Direction::Up), [6, 3]);
+        assert_eq!(ray((1, 2), Direction::UpLeft), [4]);
+        assert_eq!(ray((0, 1), Direction::Left), []);
+        assert_eq!(
+            grid.ray((2, 0), Direction::DownLeft).collect::<Vec<_>>(),
+            [((1, 1), &5), ((0, 2), &7)]
+        );
+    }
+}
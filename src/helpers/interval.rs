@@ -0,0 +1,240 @@
+//! Closed integer intervals and operations on sets of them.
+use std::ops::{Add, RangeInclusive, Sub};
+
+/// Integer types an [`Interval`] can be built from.
+pub trait Bound: Copy + Ord + Add<Output = Self> + Sub<Output = Self> + From<u8> {}
+
+impl<T: Copy + Ord + Add<Output = T> + Sub<Output = T> + From<u8>> Bound for T {}
+
+/// Closed interval `start..=end` that is never empty.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Interval<T> {
+    start: T,
+    end: T,
+}
+
+impl<T: Bound> Interval<T> {
+    /// Panics if `start > end`.
+    pub fn new(start: T, end: T) -> Self {
+        assert!(start <= end, "Interval start must not be after its end");
+        Self { start, end }
+    }
+
+    pub fn start(&self) -> T {
+        self.start
+    }
+
+    pub fn end(&self) -> T {
+        self.end
+    }
+
+    /// Number of values in the interval.
+    pub fn len(&self) -> T {
+        self.end - self.start + T::from(1)
+    }
+
+    pub fn contains(&self, value: T) -> bool {
+        self.start <= value && value <= self.end
+    }
+
+    /// Whether `other` lies entirely within `self`.
+    pub fn contains_interval(&self, other: &Self) -> bool {
+        self.start <= other.start && other.end <= self.end
+    }
+
+    pub fn overlaps(&self, other: &Self) -> bool {
+        self.start <= other.end && other.start <= self.end
+    }
+
+    pub fn intersection(&self, other: &Self) -> Option<Self> {
+        self.overlaps(other).then(|| Self {
+            start: self.start.max(other.start),
+            end: self.end.min(other.end),
+        })
+    }
+
+    /// Whether the union of both intervals is an interval again, i.e. they overlap or touch.
+    fn touches(&self, other: &Self) -> bool {
+        self.overlaps(other)
+            || (self.end < other.start && self.end + T::from(1) == other.start)
+            || (other.end < self.start && other.end + T::from(1) == self.start)
+    }
+
+    /// The union of both intervals, or `None` if there is a gap between them.
+    pub fn union(&self, other: &Self) -> Option<Self> {
+        self.touches(other).then(|| Self {
+            start: self.start.min(other.start),
+            end: self.end.max(other.end),
+        })
+    }
+}
+
+impl<T: Bound> From<RangeInclusive<T>> for Interval<T> {
+    fn from(range: RangeInclusive<T>) -> Self {
+        let (start, end) = range.into_inner();
+        Self::new(start, end)
+    }
+}
+
+impl<T: Bound> From<Interval<T>> for RangeInclusive<T> {
+    fn from(interval: Interval<T>) -> Self {
+        interval.start..=interval.end
+    }
+}
+
+/// Merges overlapping and adjacent intervals into a sorted list of disjoint intervals.
+pub fn merge<T: Bound>(intervals: impl IntoIterator<Item = Interval<T>>) -> Vec<Interval<T>> {
+    let mut sorted: Vec<_> = intervals.into_iter().collect();
+    sorted.sort_unstable();
+
+    let mut merged: Vec<Interval<T>> = Vec::with_capacity(sorted.len());
+    for interval in sorted {
+        match merged.last_mut() {
+            Some(last) if last.touches(&interval) => last.end = last.end.max(interval.end),
+            _ => merged.push(interval),
+        }
+    }
+    merged
+}
+
+/// Number of distinct values covered by any of the intervals.
+pub fn coverage<T: Bound>(intervals: impl IntoIterator<Item = Interval<T>>) -> T {
+    merge(intervals)
+        .iter()
+        .fold(T::from(0), |acc, interval| acc + interval.len())
+}
+
+/// Values between the first start and the last end that no interval covers.
+pub fn gaps<T: Bound>(intervals: impl IntoIterator<Item = Interval<T>>) -> Vec<Interval<T>> {
+    merge(intervals)
+        .windows(2)
+        .map(|pair| Interval::new(pair[0].end + T::from(1), pair[1].start - T::from(1)))
+        .collect()
+}
+
+/// Values covered by at least `count` of the intervals, as sorted disjoint intervals.
+pub fn covered_at_least<T: Bound>(
+    intervals: impl IntoIterator<Item = Interval<T>>,
+    count: usize,
+) -> Vec<Interval<T>> {
+    // Sweep over the boundaries: an interval is entered at its start and left after its end.
+    // Ends stay inclusive so that intervals ending at `T::MAX` don't overflow.
+    let mut starts = vec![];
+    let mut ends = vec![];
+    for interval in intervals {
+        starts.push(interval.start);
+        ends.push(interval.end);
+    }
+    starts.sort_unstable();
+    ends.sort_unstable();
+
+    let mut result: Vec<Interval<T>> = vec![];
+    let mut depth = 0;
+    let mut region_start = None;
+    let (mut s, mut e) = (0, 0);
+
+    while e < ends.len() {
+        if s < starts.len() && starts[s] <= ends[e] {
+            depth += 1;
+            if depth == count {
+                region_start = Some(starts[s]);
+            }
+            s += 1;
+        } else {
+            if depth == count {
+                if let Some(start) = region_start.take() {
+                    let region = Interval::new(start, ends[e]);
+                    match result.last_mut() {
+                        Some(last) if last.touches(&region) => last.end = region.end,
+                        _ => result.push(region),
+                    }
+                }
+            }
+            depth -= 1;
+            e += 1;
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn iv(start: u32, end: u32) -> Interval<u32> {
+        Interval::new(start, end)
+    }
+
+    #[test]
+    fn test_pairwise() {
+        assert!(iv(2, 8).contains_interval(&iv(3, 7)));
+        assert!(!iv(3, 7).contains_interval(&iv(2, 8)));
+        assert!(iv(5, 7).overlaps(&iv(7, 9)));
+        assert!(!iv(2, 4).overlaps(&iv(6, 8)));
+        assert_eq!(iv(2, 6).intersection(&iv(4, 8)), Some(iv(4, 6)));
+        assert_eq!(iv(2, 3).intersection(&iv(4, 8)), None);
+        assert_eq!(iv(2, 3).union(&iv(4, 8)), Some(iv(2, 8)));
+        assert_eq!(iv(2, 3).union(&iv(5, 8)), None);
+        assert_eq!(iv(4, 8).len(), 5);
+    }
+
+    #[test]
+    fn test_merge_coverage_gaps() {
+        let intervals = [iv(10, 12), iv(1, 3), iv(2, 5), iv(6, 6), iv(20, 20)];
+        assert_eq!(merge(intervals), vec![iv(1, 6), iv(10, 12), iv(20, 20)]);
+        assert_eq!(coverage(intervals), 10);
+        assert_eq!(gaps(intervals), vec![iv(7, 9), iv(13, 19)]);
+        assert_eq!(coverage(Vec::<Interval<u32>>::new()), 0);
+    }
+
+    #[test]
+    fn test_covered_at_least() {
+        let intervals = [
+            iv(1, 5),
+            iv(3, 8),
+            iv(4, 4),
+            iv(8, 10),
+            iv(11, 12),
+            iv(12, 12),
+        ];
+        assert_eq!(covered_at_least(intervals, 1), vec![iv(1, 12)]);
+        assert_eq!(
+            covered_at_least(intervals, 2),
+            vec![iv(3, 5), iv(8, 8), iv(12, 12)]
+        );
+        assert_eq!(covered_at_least(intervals, 3), vec![iv(4, 4)]);
+        assert_eq!(covered_at_least(intervals, 4), vec![]);
+        assert_eq!(
+            covered_at_least([iv(1, 2), iv(3, 4), iv(3, 4)], 2),
+            vec![iv(3, 4)]
+        );
+        assert_eq!(
+            covered_at_least([iv(0, u32::MAX), iv(7, u32::MAX)], 2),
+            vec![iv(7, u32::MAX)]
+        );
+    }
+
+    #[test]
+    fn test_covered_at_least_matches_counting() {
+        let mut rng = crate::generators::Rng::new(3);
+        for _ in 0..200 {
+            let intervals: Vec<_> = (0..rng.below(8))
+                .map(|_| {
+                    let start = rng.range(0..=30) as u32;
+                    iv(start, start + rng.range(0..=10) as u32)
+                })
+                .collect();
+
+            for count in 1..=3 {
+                let expected: Vec<u32> = (0..=40)
+                    .filter(|&v| intervals.iter().filter(|i| i.contains(v)).count() >= count)
+                    .collect();
+                let actual: Vec<u32> = covered_at_least(intervals.iter().copied(), count)
+                    .into_iter()
+                    .flat_map(RangeInclusive::from)
+                    .collect();
+                assert_eq!(actual, expected);
+            }
+        }
+    }
+}
@@ -0,0 +1,79 @@
+//! Selection of the largest items of a sequence without sorting all of it.
+use std::{cmp::Reverse, collections::BinaryHeap};
+
+/// Keeps the `k` largest items pushed so far in a min-heap of at most `k` items,
+/// so selecting from `n` items takes `O(n log k)` time and `O(k)` memory.
+#[derive(Clone, Debug)]
+pub struct TopK<T> {
+    k: usize,
+    heap: BinaryHeap<Reverse<T>>,
+}
+
+impl<T: Ord> TopK<T> {
+    pub fn new(k: usize) -> Self {
+        Self {
+            k,
+            heap: BinaryHeap::new(),
+        }
+    }
+
+    pub fn push(&mut self, item: T) {
+        if self.heap.len() < self.k {
+            self.heap.push(Reverse(item));
+        } else if let Some(mut smallest) = self.heap.peek_mut() {
+            if item > smallest.0 {
+                smallest.0 = item;
+            }
+        }
+    }
+
+    /// The kept items, largest first.
+    pub fn into_sorted_vec(self) -> Vec<T> {
+        // Sorting `Reverse` ascending puts the largest items first.
+        self.heap
+            .into_sorted_vec()
+            .into_iter()
+            .map(|item| item.0)
+            .collect()
+    }
+}
+
+impl<T: Ord> Extend<T> for TopK<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        iter.into_iter().for_each(|item| self.push(item));
+    }
+}
+
+/// The `k` largest items of `iter`, largest first.
+pub fn top_k<T: Ord>(iter: impl IntoIterator<Item = T>, k: usize) -> Vec<T> {
+    let mut top = TopK::new(k);
+    top.extend(iter);
+    top.into_sorted_vec()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_top_k() {
+        assert_eq!(top_k([5, 1, 9, 3, 7, 9], 3), vec![9, 9, 7]);
+        assert_eq!(top_k([5, 1], 3), vec![5, 1]);
+        assert_eq!(top_k([5, 1], 0), Vec::<i32>::new());
+        assert_eq!(top_k(Vec::<i32>::new(), 2), Vec::<i32>::new());
+    }
+
+    #[test]
+    fn test_matches_sorting() {
+        let mut rng = crate::generators::Rng::new(1);
+        for _ in 0..100 {
+            let items: Vec<u64> = (0..rng.below(50)).map(|_| rng.range(0..=20)).collect();
+            let k = rng.below(10);
+
+            let mut sorted = items.clone();
+            sorted.sort_unstable_by(|a, b| b.cmp(a));
+            sorted.truncate(k);
+            assert_eq!(top_k(items, k), sorted);
+        }
+    }
+}
@@ -4,25 +4,35 @@
  * Prefer `./helpers.rs` if you want to extract code from your solutions.
  */
 use std::env;
+use std::fmt::Display;
 use std::fs;
+use std::process;
 
+pub mod allocator;
+pub mod generators;
 pub mod helpers;
 
+#[cfg(feature = "count-allocations")]
+#[global_allocator]
+static GLOBAL: allocator::CountingAllocator = allocator::CountingAllocator;
+
 pub const ANSI_ITALIC: &str = "\x1b[3m";
 pub const ANSI_BOLD: &str = "\x1b[1m";
 pub const ANSI_RESET: &str = "\x1b[0m";
 
 #[macro_export]
 macro_rules! solve {
-    ($part:expr, $solver:ident, $input:expr) => {{
+    ($part:expr, $solver:expr, $input:expr) => {{
         use advent_of_code::{ANSI_BOLD, ANSI_ITALIC, ANSI_RESET};
         use std::fmt::Display;
         use std::time::Instant;
 
-        fn print_result<T: Display>(func: impl FnOnce(&str) -> Option<T>, input: &str) {
+        fn print_result<I: ?Sized, T: Display>(func: impl FnOnce(&I) -> Option<T>, input: &I) {
+            advent_of_code::allocator::reset();
             let timer = Instant::now();
             let result = func(input);
             let elapsed = timer.elapsed();
+            let alloc_stats = advent_of_code::allocator::stats();
             match result {
                 Some(result) => {
                     println!(
@@ -34,6 +44,9 @@ macro_rules! solve {
                     println!("not solved.")
                 }
             }
+            if let Some(stats) = alloc_stats {
+                println!("{}{}{}", ANSI_ITALIC, stats, ANSI_RESET);
+            }
         }
 
         println!("🎄 {}Part {}{} 🎄", ANSI_BOLD, $part, ANSI_RESET);
@@ -41,13 +54,72 @@ macro_rules! solve {
     }};
 }
 
+/// Runs the parse step of a solution and prints its timing.
+/// The parsed value is returned so that both parts can share it, e.g.
+/// ```ignore
+/// let grid = advent_of_code::parse!(parse, input);
+/// advent_of_code::solve!(1, part_one, &grid);
+/// advent_of_code::solve!(2, part_two, &grid);
+/// ```
+#[macro_export]
+macro_rules! parse {
+    ($parser:expr, $input:expr) => {{
+        use advent_of_code::{ANSI_BOLD, ANSI_ITALIC, ANSI_RESET};
+        use std::time::Instant;
+
+        fn run_parser<'a, I: ?Sized, T>(func: impl FnOnce(&'a I) -> T, input: &'a I) -> T {
+            advent_of_code::allocator::reset();
+            let timer = Instant::now();
+            let parsed = func(input);
+            let elapsed = timer.elapsed();
+            let alloc_stats = advent_of_code::allocator::stats();
+            println!("{}(elapsed: {:.2?}){}", ANSI_ITALIC, elapsed, ANSI_RESET);
+            if let Some(stats) = alloc_stats {
+                println!("{}{}{}", ANSI_ITALIC, stats, ANSI_RESET);
+            }
+            parsed
+        }
+
+        println!("🎄 {}Parse{} 🎄", ANSI_BOLD, ANSI_RESET);
+        run_parser($parser, $input)
+    }};
+}
+
+/// Unwraps the result of a fallible parse step, or prints the error and exits.
+pub fn unwrap_or_exit<T, E: Display>(result: Result<T, E>) -> T {
+    result.unwrap_or_else(|err| {
+        eprintln!("Invalid input: {err}");
+        process::exit(1);
+    })
+}
+
+/// Reads an input or example file and passes it through [`normalize_input`].
 pub fn read_file(folder: &str, day: u8) -> String {
     let cwd = env::current_dir().unwrap();
 
     let filepath = cwd.join("src").join(folder).join(format!("{:02}.txt", day));
 
     let f = fs::read_to_string(filepath);
-    f.expect("could not open input file")
+    normalize_input(&f.expect("could not open input file"))
+}
+
+/// Brings input into the shape that solutions expect, regardless of editor or OS:
+/// no byte order mark, `\n` line endings only, and exactly one `\n` after the last line.
+/// Whitespace at the end of the input is dropped, whitespace within lines is kept.
+pub fn normalize_input(input: &str) -> String {
+    let input = input.strip_prefix('\u{feff}').unwrap_or(input);
+
+    let mut normalized = if input.contains('\r') {
+        input.replace("\r\n", "\n").replace('\r', "\n")
+    } else {
+        input.to_string()
+    };
+
+    normalized.truncate(normalized.trim_end().len());
+    if !normalized.is_empty() {
+        normalized.push('\n');
+    }
+    normalized
 }
 
 fn parse_time(val: &str, postfix: &str) -> f64 {
@@ -95,6 +167,20 @@ macro_rules! assert_approx_eq {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_normalize_input() {
+        assert_eq!(normalize_input("A Y\nB X\nC Z\n"), "A Y\nB X\nC Z\n");
+        assert_eq!(normalize_input("A Y\nB X\nC Z"), "A Y\nB X\nC Z\n");
+        assert_eq!(normalize_input("A Y\r\nB X\r\nC Z\r\n"), "A Y\nB X\nC Z\n");
+        assert_eq!(normalize_input("A Y\rB X\n\n\n  \n"), "A Y\nB X\n");
+        assert_eq!(
+            normalize_input("\u{feff}1000\r\n\r\n2000"),
+            "1000\n\n2000\n"
+        );
+        assert_eq!(normalize_input("    [D]    \n 1 \n"), "    [D]    \n 1\n");
+        assert_eq!(normalize_input("\n \r\n"), "");
+    }
+
     #[test]
     fn test_parse_exec_time() {
         assert_approx_eq!(
@@ -121,5 +207,12 @@ mod tests {
             ),
             10400.50_f64
         );
+
+        assert_approx_eq!(
+            parse_exec_time(
+                "🎄 Parse 🎄\n(elapsed: 300µs)\n🎄 Part 1 🎄\n0 (elapsed: 70µs)\n🎄 Part 2 🎄\n0 (elapsed: 1.45ms)"
+            ),
+            1.82_f64
+        );
     }
 }
@@ -10,10 +10,12 @@ fn main() {
         .map(|day| {
             let day = format!("{:02}", day);
 
-            let cmd = Command::new("cargo")
-                .args(["run", "--release", "--bin", &day])
-                .output()
-                .unwrap();
+            let mut cmd = Command::new("cargo");
+            cmd.args(["run", "--release", "--bin", &day]);
+            if advent_of_code::allocator::enabled() {
+                cmd.args(["--features", "count-allocations"]);
+            }
+            let cmd = cmd.output().unwrap();
 
             println!("----------");
             println!("{}| Day {} |{}", ANSI_BOLD, day, ANSI_RESET);